@@ -0,0 +1,72 @@
+//! Full scan of a generated library with thousands of fake dumps, on one
+//! thread and on all of them. Run with `cargo bench --bench scan`.
+
+use criterion::{criterion_group, criterion_main, Criterion};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+const ROOTS: usize = 8;
+const DUMPS_PER_ROOT: usize = 500;
+
+/// Game folders like a real collection: dumps grouped in subfolders, some
+/// in a folder of their own with cover art
+fn generate_library(dir: &Path) -> Vec<PathBuf> {
+    (0..ROOTS)
+        .map(|r| {
+            let root = dir.join(format!("Library {}", r));
+            for i in 0..DUMPS_PER_ROOT {
+                let title_id = format!("0100{:08X}0000", r * DUMPS_PER_ROOT + i);
+                let ext = ["nsp", "xci", "nro"][i % 3];
+                let stem = format!("Game {} [{}][v0]", i, title_id);
+
+                let folder = root.join(format!("Series {}", i % 25));
+                fs::create_dir_all(&folder).unwrap();
+                if i % 5 == 0 {
+                    let game_folder = folder.join(&stem);
+                    fs::create_dir_all(&game_folder).unwrap();
+                    fs::write(game_folder.join("cover.jpg"), [0xFF, 0xD8, 0xFF, 0xD9]).unwrap();
+                }
+
+                // A header that is read but isn't a valid container
+                let mut data = b"PFS0".to_vec();
+                data.resize(4096, 0);
+                fs::write(folder.join(format!("{}.{}", stem, ext)), data).unwrap();
+            }
+            root
+        })
+        .collect()
+}
+
+fn scan(c: &mut Criterion) {
+    let dir = tempfile::tempdir().unwrap();
+    let roots = generate_library(dir.path());
+    let expected = ROOTS * DUMPS_PER_ROOT;
+
+    let mut group = c.benchmark_group("scan");
+    group.sample_size(10);
+
+    let single = rayon::ThreadPoolBuilder::new()
+        .num_threads(1)
+        .build()
+        .unwrap();
+    group.bench_function("one thread", |b| {
+        b.iter(|| {
+            let found = single
+                .install(|| app_lib::bench::scan_roots(&roots, 3))
+                .unwrap();
+            assert_eq!(found, expected);
+        })
+    });
+
+    group.bench_function("parallel", |b| {
+        b.iter(|| {
+            let found = app_lib::bench::scan_roots(&roots, 3).unwrap();
+            assert_eq!(found, expected);
+        })
+    });
+
+    group.finish();
+}
+
+criterion_group!(benches, scan);
+criterion_main!(benches);
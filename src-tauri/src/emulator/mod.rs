@@ -0,0 +1,450 @@
+//! Emulators we can scan for and launch games with
+
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+use walkdir::WalkDir;
+
+mod profile;
+mod ryujinx;
+mod yuzu;
+
+pub use profile::{EmulatorProfile, LaunchOverrides, LaunchTarget};
+
+/// How deep to look for games in folders without a configured depth
+pub const DEFAULT_SCAN_DEPTH: usize = 3;
+
+/// A folder to look for games in
+#[derive(Debug, Clone, PartialEq)]
+pub struct GameDir {
+    pub path: PathBuf,
+    /// WalkDir depth; 1 searches only the folder itself
+    pub max_depth: usize,
+}
+
+impl GameDir {
+    /// A folder searched to the default depth
+    pub fn new(path: PathBuf) -> Self {
+        GameDir {
+            path,
+            max_depth: DEFAULT_SCAN_DEPTH,
+        }
+    }
+
+    /// A folder the user added in the emulator, with its "deep scan"
+    /// (search subfolders) setting
+    pub fn configured(path: PathBuf, deep_scan: bool) -> Self {
+        GameDir {
+            path,
+            max_depth: if deep_scan { usize::MAX } else { 1 },
+        }
+    }
+}
+
+/// What an emulator's own data says about a title
+#[derive(Debug, Clone, Default)]
+pub struct TitleMetadata {
+    pub name: Option<String>,
+    /// Icon as a data URL
+    pub icon: Option<String>,
+    pub favorite: Option<bool>,
+    pub playtime: Option<Playtime>,
+}
+
+/// Playtime an emulator recorded for a title
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default, PartialEq)]
+pub struct Playtime {
+    pub seconds: u64,
+    /// Seconds since the Unix epoch
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_played: Option<u64>,
+}
+
+/// An emulator family member. Implementations know where the emulator
+/// keeps its data, how to read its config and how to start it.
+pub trait Emulator: Sync {
+    /// Stable ID stored in `Game::emulator`, e.g. "yuzu"
+    fn id(&self) -> &'static str;
+
+    /// Display name, e.g. "Ryujinx"
+    fn name(&self) -> &'static str;
+
+    /// User data directories, standard and portable, that may exist
+    fn data_dirs(&self, locations: &Locations) -> Vec<PathBuf>;
+
+    /// Directories that may hold `prod.keys` and `title.keys`
+    fn key_dirs(&self, locations: &Locations) -> Vec<PathBuf> {
+        self.data_dirs(locations)
+            .into_iter()
+            .map(|dir| dir.join("keys"))
+            .collect()
+    }
+
+    /// Game folders from the emulator's config and its data directories
+    fn game_dirs(&self, locations: &Locations) -> Vec<GameDir>;
+
+    /// Common places to keep games that aren't set up anywhere, scanned
+    /// unless the user turned built-in locations off
+    fn default_game_dirs(&self, _locations: &Locations) -> Vec<GameDir> {
+        Vec::new()
+    }
+
+    /// Names, icons and the like the emulator stores per title ID
+    fn title_metadata(&self, _locations: &Locations) -> HashMap<String, TitleMetadata> {
+        HashMap::new()
+    }
+
+    /// Mark a title as a favorite in the emulator's own data. Returns
+    /// whether the emulator had anything to update.
+    fn set_favorite(
+        &self,
+        _locations: &Locations,
+        _title_id: &str,
+        _favorite: bool,
+    ) -> Result<bool, String> {
+        Ok(false)
+    }
+
+    /// Executable file names, most specific first
+    fn executable_names(&self) -> &'static [&'static str];
+
+    /// Where installers put the executable
+    fn install_dirs(&self) -> Vec<PathBuf>;
+
+    /// Argument template to boot a game, see [`EmulatorProfile`]
+    fn arg_template(&self) -> &'static [&'static str];
+
+    /// Arguments `{fullscreen}` expands to
+    fn fullscreen_args(&self) -> &'static [&'static str];
+
+    /// Whether games start fullscreen unless the user says otherwise
+    fn fullscreen_by_default(&self) -> bool {
+        false
+    }
+
+    /// The profile used when the user has not defined one for this ID.
+    /// The executable may be found in portable installs in `locations`.
+    fn default_profile(&self, locations: Option<&Locations>) -> EmulatorProfile {
+        EmulatorProfile {
+            executable: self.resolve_executable(locations),
+            ..self.unresolved_profile()
+        }
+    }
+
+    /// `default_profile` with a bare executable name instead of looking
+    /// for the executable on disk
+    fn unresolved_profile(&self) -> EmulatorProfile {
+        let to_strings = |args: &[&str]| args.iter().map(|a| a.to_string()).collect();
+        EmulatorProfile {
+            id: self.id().to_string(),
+            name: self.name().to_string(),
+            executable: PathBuf::from(executable_file_name(self.executable_names()[0])),
+            working_dir: None,
+            args: to_strings(self.arg_template()),
+            fullscreen_args: to_strings(self.fullscreen_args()),
+            fullscreen: self.fullscreen_by_default(),
+            user: None,
+            env: BTreeMap::new(),
+        }
+    }
+
+    /// Find the executable: `<ID>_PATH` if set, then install locations and
+    /// portable copies in Downloads, then a bare name for a PATH lookup
+    fn resolve_executable(&self, locations: Option<&Locations>) -> PathBuf {
+        let names: Vec<String> = self
+            .executable_names()
+            .iter()
+            .map(|name| executable_file_name(name))
+            .collect();
+
+        let env_var = format!("{}_PATH", self.id().to_ascii_uppercase());
+        if let Some(env_path) = std::env::var_os(&env_var).map(PathBuf::from) {
+            if env_path.is_dir() {
+                if let Some(path) = names.iter().map(|n| env_path.join(n)).find(|p| p.exists()) {
+                    return path;
+                }
+            } else {
+                return env_path;
+            }
+        }
+
+        let installed = self
+            .install_dirs()
+            .into_iter()
+            .flat_map(|dir| names.iter().map(move |name| dir.join(name)))
+            .find(|path| path.is_file());
+        if let Some(path) = installed {
+            return path;
+        }
+
+        if let Some(path) =
+            locations.and_then(|locations| find_portable_executable(locations, self.id(), &names))
+        {
+            return path;
+        }
+
+        PathBuf::from(&names[0])
+    }
+}
+
+/// Every emulator we know about, in scan order
+static EMULATORS: &[&dyn Emulator] = &[
+    &yuzu::YUZU,
+    &yuzu::SUYU,
+    &yuzu::SUDACHI,
+    &yuzu::CITRON,
+    &yuzu::EDEN,
+    &yuzu::TORZU,
+    &ryujinx::RYUJINX,
+];
+
+pub fn all() -> &'static [&'static dyn Emulator] {
+    EMULATORS
+}
+
+/// Look up an emulator by ID, case-insensitively
+pub fn get(id: &str) -> Option<&'static dyn Emulator> {
+    EMULATORS
+        .iter()
+        .copied()
+        .find(|e| e.id().eq_ignore_ascii_case(id))
+}
+
+/// The profile to launch `id` with: the user's profile with that ID, or
+/// the built-in emulator's default
+pub fn profile(
+    id: &str,
+    user_profiles: &[EmulatorProfile],
+    locations: Option<&Locations>,
+) -> Option<EmulatorProfile> {
+    user_profiles
+        .iter()
+        .find(|p| p.id.eq_ignore_ascii_case(id))
+        .cloned()
+        .or_else(|| get(id).map(|e| e.default_profile(locations)))
+}
+
+/// Like `profile`, but without looking for built-in emulators'
+/// executables. Enough to check arguments; launching needs `profile`.
+pub fn unresolved_profile(id: &str, user_profiles: &[EmulatorProfile]) -> Option<EmulatorProfile> {
+    user_profiles
+        .iter()
+        .find(|p| p.id.eq_ignore_ascii_case(id))
+        .cloned()
+        .or_else(|| get(id).map(|e| e.unresolved_profile()))
+}
+
+/// Whether `id` names a built-in emulator or one of the user's profiles
+pub fn is_known(id: &str, user_profiles: &[EmulatorProfile]) -> bool {
+    get(id).is_some() || user_profiles.iter().any(|p| p.id.eq_ignore_ascii_case(id))
+}
+
+/// Emulator summary for the UI
+#[derive(Debug, Serialize, Clone)]
+pub struct EmulatorInfo {
+    pub id: String,
+    pub name: String,
+    /// Defined by the user rather than built in
+    pub custom: bool,
+}
+
+/// Built-in emulators followed by user profiles for other emulators.
+/// User profiles that replace a built-in take its place and name.
+pub fn list(user_profiles: &[EmulatorProfile]) -> Vec<EmulatorInfo> {
+    let mut emulators: Vec<EmulatorInfo> = EMULATORS
+        .iter()
+        .map(|e| EmulatorInfo {
+            id: e.id().to_string(),
+            name: e.name().to_string(),
+            custom: false,
+        })
+        .collect();
+
+    for profile in user_profiles {
+        match emulators
+            .iter_mut()
+            .find(|e| e.id.eq_ignore_ascii_case(&profile.id))
+        {
+            Some(existing) => {
+                existing.name = profile.name.clone();
+                existing.custom = true;
+            }
+            None => emulators.push(EmulatorInfo {
+                id: profile.id.clone(),
+                name: profile.name.clone(),
+                custom: true,
+            }),
+        }
+    }
+    emulators
+}
+
+/// `name.exe` on Windows, `name` elsewhere
+fn executable_file_name(name: &str) -> String {
+    if cfg!(target_os = "windows") {
+        format!("{}.exe", name)
+    } else {
+        name.to_string()
+    }
+}
+
+/// Where emulators may keep their files on this machine: the home folder
+/// and portable installs unpacked in Downloads. Finding portable installs
+/// walks Downloads, so it is done once and shared by every emulator.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Locations {
+    pub home_dir: PathBuf,
+    /// Files in Downloads (limited depth) and the emulator whose folder
+    /// they are in
+    portable_files: Vec<(&'static str, PathBuf)>,
+}
+
+impl Locations {
+    /// Locations under `home_dir`. Downloads is only searched for portable
+    /// installs with `search_downloads`, since users can opt out of that
+    /// walk along with the other built-in locations.
+    pub fn find(home_dir: &Path, search_downloads: bool) -> Self {
+        let downloads = home_dir.join("Downloads");
+        let portable_files = if search_downloads && downloads.exists() {
+            WalkDir::new(&downloads)
+                .max_depth(6)
+                .into_iter()
+                .filter_map(|e| e.ok())
+                .filter(|e| e.file_type().is_file())
+                .filter_map(|e| {
+                    let emulator = portable_owner(e.path().strip_prefix(&downloads).ok()?)?;
+                    Some((emulator, e.into_path()))
+                })
+                .collect()
+        } else {
+            Vec::new()
+        };
+
+        Locations {
+            home_dir: home_dir.to_path_buf(),
+            portable_files,
+        }
+    }
+
+    /// Portable files called `file_name` in a folder of `emulator_id`
+    fn portable_files(&self, file_name: &str, emulator_id: &str) -> Vec<PathBuf> {
+        self.portable_files
+            .iter()
+            .filter(|(emulator, _)| *emulator == emulator_id)
+            .map(|(_, path)| path)
+            .filter(|path| {
+                path.file_name()
+                    .and_then(|name| name.to_str())
+                    .is_some_and(|name| name.eq_ignore_ascii_case(file_name))
+            })
+            .cloned()
+            .collect()
+    }
+}
+
+/// The emulator a file under Downloads belongs to. A folder names an
+/// emulator when it is the ID, or the ID followed by `-` or `_` like
+/// `citron-windows`; the innermost such folder wins, so `yuzu/torzu/`
+/// is Torzu's.
+fn portable_owner(relative: &Path) -> Option<&'static str> {
+    relative.parent()?.components().rev().find_map(|component| {
+        let name = component.as_os_str().to_str()?.to_ascii_lowercase();
+        EMULATORS.iter().map(|e| e.id()).find(|id| {
+            name.strip_prefix(id)
+                .is_some_and(|rest| rest.is_empty() || rest.starts_with(['-', '_']))
+        })
+    })
+}
+
+fn find_portable_executable(
+    locations: &Locations,
+    emulator_id: &str,
+    names: &[String],
+) -> Option<PathBuf> {
+    names.iter().find_map(|name| {
+        locations
+            .portable_files(name, emulator_id)
+            .into_iter()
+            .next()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_registry() {
+        let mut ids: Vec<_> = all().iter().map(|e| e.id()).collect();
+        let count = ids.len();
+        ids.sort();
+        ids.dedup();
+        assert_eq!(ids.len(), count, "emulator IDs must be unique");
+
+        assert_eq!(get("Sudachi").unwrap().name(), "Sudachi");
+        assert_eq!(get("ryujinx").unwrap().id(), "ryujinx");
+        assert!(get("cemu").is_none());
+    }
+
+    #[test]
+    fn test_user_profiles() {
+        let custom = EmulatorProfile {
+            id: "cemu".to_string(),
+            name: "Wrapper".to_string(),
+            ..get("yuzu").unwrap().default_profile(None)
+        };
+        let profiles = vec![custom.clone()];
+
+        assert_eq!(profile("cemu", &profiles, None), Some(custom));
+        let yuzu = profile("yuzu", &profiles, None).unwrap();
+        assert_eq!(yuzu.args, vec!["{fullscreen}", "-g", "{path}"]);
+        assert!(yuzu.fullscreen);
+        assert!(profile("unknown", &profiles, None).is_none());
+        assert!(is_known("CEMU", &profiles) && is_known("ryujinx", &profiles));
+        assert!(!is_known("unknown", &profiles));
+        assert_eq!(
+            unresolved_profile("yuzu", &profiles).unwrap().args,
+            yuzu.args
+        );
+
+        let emulators = list(&profiles);
+        assert_eq!(emulators.len(), all().len() + 1);
+        assert!(emulators.last().unwrap().custom);
+    }
+
+    #[test]
+    fn test_resolve_portable_executable() {
+        let home = tempfile::tempdir().unwrap();
+        let exe = home
+            .path()
+            .join("Downloads/citron-windows/citron")
+            .join(executable_file_name("citron"));
+        std::fs::create_dir_all(exe.parent().unwrap()).unwrap();
+        std::fs::write(&exe, b"").unwrap();
+
+        let names = vec![executable_file_name("citron")];
+        let locations = Locations::find(home.path(), true);
+        let found = find_portable_executable(&locations, "citron", &names);
+        assert_eq!(found, Some(exe));
+        let found = find_portable_executable(&locations, "eden", &names);
+        assert!(found.is_none());
+
+        let locations = Locations::find(home.path(), false);
+        assert!(find_portable_executable(&locations, "citron", &names).is_none());
+    }
+
+    #[test]
+    fn test_portable_owner() {
+        let owner = |path: &str| portable_owner(Path::new(path));
+        assert_eq!(owner("citron-windows/citron/citron.exe"), Some("citron"));
+        assert_eq!(owner("Eden_v0.1/eden.exe"), Some("eden"));
+        assert_eq!(owner("yuzu-early-access/yuzu.exe"), Some("yuzu"));
+        // Torzu ships yuzu.exe, and forks may sit in a yuzu folder
+        assert_eq!(owner("torzu/yuzu.exe"), Some("torzu"));
+        assert_eq!(owner("yuzu/torzu-linux/qt-config.ini"), Some("torzu"));
+        // IDs inside other words or names don't count
+        assert_eq!(owner("Garden of Eden/eden.exe"), None);
+        assert_eq!(owner("yuzuroms/yuzu.exe"), None);
+        assert_eq!(owner("yuzu.exe"), None);
+    }
+}
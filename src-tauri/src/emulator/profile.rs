@@ -0,0 +1,248 @@
+//! User-defined emulator profiles: how to start an emulator for a game
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// An executable with an argument template. Built-in emulators provide a
+/// default profile; user profiles with the same ID replace it.
+///
+/// Each template argument may contain `{path}`, `{title_id}` and `{user}`.
+/// An argument that is exactly `{fullscreen}` expands to `fullscreen_args`
+/// when `fullscreen` is set and to nothing otherwise. Arguments that expand
+/// to an empty string are dropped.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct EmulatorProfile {
+    /// Matched against `Game::emulator`
+    pub id: String,
+    pub name: String,
+    pub executable: PathBuf,
+    /// Defaults to the executable's directory
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub working_dir: Option<PathBuf>,
+    #[serde(default)]
+    pub args: Vec<String>,
+    #[serde(default)]
+    pub fullscreen_args: Vec<String>,
+    #[serde(default)]
+    pub fullscreen: bool,
+    /// Emulator user profile to boot with, substituted for `{user}`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub user: Option<String>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub env: BTreeMap<String, String>,
+}
+
+/// Per-game changes to how a game is started, kept in the library
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+pub struct LaunchOverrides {
+    /// Profile ID to use instead of the emulator the game was found for
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub emulator: Option<String>,
+    /// Appended to the profile's arguments; may use the same placeholders
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub args: Vec<String>,
+    /// Added to the profile's environment, replacing variables it sets
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub env: BTreeMap<String, String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub working_dir: Option<PathBuf>,
+}
+
+/// Game-specific values for the template placeholders
+#[derive(Debug, Clone, Copy)]
+pub struct LaunchTarget<'a> {
+    pub path: &'a Path,
+    pub title_id: Option<&'a str>,
+}
+
+impl EmulatorProfile {
+    /// Check the profile is usable before saving it
+    pub fn validate(&self) -> Result<(), String> {
+        if self.id.trim().is_empty() {
+            return Err("Profile ID is empty".to_string());
+        }
+        if self.executable.as_os_str().is_empty() {
+            return Err(format!("Profile {} has no executable", self.id));
+        }
+        let target = LaunchTarget {
+            path: Path::new("game.nsp"),
+            title_id: Some("0100000000000000"),
+        };
+        self.expand_args(&target).map(|_| ())
+    }
+
+    /// This profile with a game's overrides applied. `overrides.emulator`
+    /// is not looked at; it picks the profile in the first place.
+    pub fn with_overrides(mut self, overrides: &LaunchOverrides) -> Self {
+        self.args.extend(overrides.args.iter().cloned());
+        self.env.extend(overrides.env.clone());
+        if let Some(dir) = &overrides.working_dir {
+            self.working_dir = Some(dir.clone());
+        }
+        self
+    }
+
+    /// Substitute the placeholders in the argument template
+    pub fn expand_args(&self, target: &LaunchTarget) -> Result<Vec<String>, String> {
+        let mut args = Vec::new();
+        for arg in &self.args {
+            if arg == "{fullscreen}" {
+                if self.fullscreen {
+                    args.extend(self.fullscreen_args.iter().cloned());
+                }
+                continue;
+            }
+
+            let expanded = self.expand(arg, target)?;
+            if !expanded.is_empty() {
+                args.push(expanded);
+            }
+        }
+        Ok(args)
+    }
+
+    fn expand(&self, arg: &str, target: &LaunchTarget) -> Result<String, String> {
+        let mut out = String::new();
+        let mut rest = arg;
+        while let Some(start) = rest.find('{') {
+            out.push_str(&rest[..start]);
+            let Some(len) = rest[start..].find('}') else {
+                return Err(format!("Unclosed placeholder in argument {:?}", arg));
+            };
+            let name = &rest[start + 1..start + len];
+            match name {
+                "path" => out.push_str(&target.path.to_string_lossy()),
+                "title_id" => out.push_str(target.title_id.unwrap_or_default()),
+                "user" => out.push_str(self.user.as_deref().unwrap_or_default()),
+                _ => return Err(format!("Unknown placeholder {{{}}}", name)),
+            }
+            rest = &rest[start + len + 1..];
+        }
+        out.push_str(rest);
+        Ok(out)
+    }
+
+    /// Build the command that launches `target` with this profile
+    pub fn command(&self, target: &LaunchTarget) -> Result<Command, String> {
+        let mut cmd = Command::new(&self.executable);
+        cmd.args(self.expand_args(target)?);
+        cmd.envs(&self.env);
+
+        let working_dir = self.working_dir.as_deref().or_else(|| {
+            self.executable
+                .parent()
+                .filter(|p| !p.as_os_str().is_empty())
+        });
+        if let Some(dir) = working_dir {
+            cmd.current_dir(dir);
+        }
+        Ok(cmd)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::ffi::OsStr;
+
+    fn profile(args: &[&str]) -> EmulatorProfile {
+        EmulatorProfile {
+            id: "custom".to_string(),
+            name: "Custom".to_string(),
+            executable: PathBuf::from("/opt/emu/bin/emu"),
+            working_dir: None,
+            args: args.iter().map(|a| a.to_string()).collect(),
+            fullscreen_args: vec!["--fullscreen".to_string()],
+            fullscreen: true,
+            user: None,
+            env: BTreeMap::new(),
+        }
+    }
+
+    #[test]
+    fn test_expand_args() {
+        let target = LaunchTarget {
+            path: Path::new("/games/game.nsp"),
+            title_id: Some("0100000000010000"),
+        };
+
+        let mut p = profile(&[
+            "{fullscreen}",
+            "--title={title_id}",
+            "-u",
+            "{user}",
+            "{path}",
+        ]);
+        assert_eq!(
+            p.expand_args(&target).unwrap(),
+            vec![
+                "--fullscreen",
+                "--title=0100000000010000",
+                "-u",
+                "/games/game.nsp"
+            ]
+        );
+
+        p.fullscreen = false;
+        p.user = Some("1".to_string());
+        assert_eq!(
+            p.expand_args(&target).unwrap(),
+            vec!["--title=0100000000010000", "-u", "1", "/games/game.nsp"]
+        );
+
+        assert!(profile(&["{rom}"]).validate().is_err());
+        assert!(profile(&["{path"]).validate().is_err());
+        assert!(profile(&["{path}"]).validate().is_ok());
+    }
+
+    #[test]
+    fn test_command() {
+        let mut p = profile(&["{path}"]);
+        p.env.insert("DXVK_HUD".to_string(), "fps".to_string());
+        let target = LaunchTarget {
+            path: Path::new("/games/game.nsp"),
+            title_id: None,
+        };
+
+        let cmd = p.command(&target).unwrap();
+        assert_eq!(cmd.get_program(), OsStr::new("/opt/emu/bin/emu"));
+        assert_eq!(cmd.get_current_dir(), Some(Path::new("/opt/emu/bin")));
+        assert_eq!(
+            cmd.get_envs().collect::<Vec<_>>(),
+            vec![(OsStr::new("DXVK_HUD"), Some(OsStr::new("fps")))]
+        );
+
+        p.working_dir = Some(PathBuf::from("/tmp"));
+        let cmd = p.command(&target).unwrap();
+        assert_eq!(cmd.get_current_dir(), Some(Path::new("/tmp")));
+    }
+
+    #[test]
+    fn test_with_overrides() {
+        let mut p = profile(&["{path}"]);
+        p.env.insert("A".to_string(), "profile".to_string());
+        p.env.insert("B".to_string(), "profile".to_string());
+
+        let overrides = LaunchOverrides {
+            emulator: None,
+            args: vec!["--config={title_id}.ini".to_string()],
+            env: BTreeMap::from([("B".to_string(), "game".to_string())]),
+            working_dir: Some(PathBuf::from("/games")),
+        };
+        let p = p.with_overrides(&overrides);
+        let target = LaunchTarget {
+            path: Path::new("/games/game.nsp"),
+            title_id: Some("0100000000010000"),
+        };
+
+        assert_eq!(
+            p.expand_args(&target).unwrap(),
+            vec!["/games/game.nsp", "--config=0100000000010000.ini"]
+        );
+        assert_eq!(p.env["A"], "profile");
+        assert_eq!(p.env["B"], "game");
+        assert_eq!(p.working_dir, Some(PathBuf::from("/games")));
+    }
+}
@@ -0,0 +1,554 @@
+//! Ryujinx and its forks, which share its `Config.json` and data layout
+
+use base64::{engine::general_purpose, Engine as _};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use walkdir::WalkDir;
+
+use super::{Emulator, GameDir, Locations, Playtime, TitleMetadata};
+
+#[derive(Debug)]
+pub struct Ryujinx;
+
+pub static RYUJINX: Ryujinx = Ryujinx;
+
+impl Emulator for Ryujinx {
+    fn id(&self) -> &'static str {
+        "ryujinx"
+    }
+
+    fn name(&self) -> &'static str {
+        "Ryujinx"
+    }
+
+    fn data_dirs(&self, locations: &Locations) -> Vec<PathBuf> {
+        let home_dir = &locations.home_dir;
+        let mut data_dirs = vec![
+            home_dir.join(".config/Ryujinx"),
+            home_dir.join("AppData/Roaming/Ryujinx"),
+            home_dir.join("Library/Application Support/Ryujinx"),
+            PathBuf::from("C:/Ryujinx/portable"),
+            home_dir.join("Ryujinx/portable"),
+        ];
+
+        // Portable installs keep their config in a `portable` folder next
+        // to the executable
+        for config_path in locations.portable_files("Config.json", self.id()) {
+            if let Some(dir) = config_path.parent() {
+                if dir.file_name() == Some("portable".as_ref()) {
+                    data_dirs.push(dir.to_path_buf());
+                }
+            }
+        }
+
+        data_dirs.sort();
+        data_dirs.dedup();
+        data_dirs
+    }
+
+    fn key_dirs(&self, locations: &Locations) -> Vec<PathBuf> {
+        self.data_dirs(locations)
+            .into_iter()
+            .map(|dir| dir.join("system"))
+            .collect()
+    }
+
+    fn game_dirs(&self, locations: &Locations) -> Vec<GameDir> {
+        self.data_dirs(locations)
+            .iter()
+            .flat_map(|dir| read_game_dirs(dir))
+            .map(GameDir::new)
+            .collect()
+    }
+
+    /// Game folders next to a portable install, scanned before Ryujinx
+    /// had a game directory setting
+    fn default_game_dirs(&self, locations: &Locations) -> Vec<GameDir> {
+        vec![
+            GameDir::new(PathBuf::from("C:/Ryujinx/games")),
+            GameDir::new(locations.home_dir.join("Ryujinx/games")),
+        ]
+    }
+
+    fn title_metadata(&self, locations: &Locations) -> HashMap<String, TitleMetadata> {
+        let mut titles = HashMap::new();
+        for data_dir in self.data_dirs(locations) {
+            for (title_id, title) in read_title_metadata(&data_dir) {
+                titles.entry(title_id).or_insert(title);
+            }
+        }
+        titles
+    }
+
+    fn set_favorite(
+        &self,
+        locations: &Locations,
+        title_id: &str,
+        favorite: bool,
+    ) -> Result<bool, String> {
+        let mut updated = false;
+        for data_dir in self.data_dirs(locations) {
+            for path in metadata_paths(&data_dir, title_id) {
+                write_favorite(&path, favorite)?;
+                updated = true;
+            }
+        }
+        Ok(updated)
+    }
+
+    fn executable_names(&self) -> &'static [&'static str] {
+        &["Ryujinx"]
+    }
+
+    fn install_dirs(&self) -> Vec<PathBuf> {
+        let mut dirs = Vec::new();
+        if let Some(local_app_data) = dirs::data_local_dir() {
+            dirs.push(local_app_data.join("Ryujinx"));
+            dirs.push(local_app_data.join("Programs").join("Ryujinx"));
+        }
+        dirs.push(PathBuf::from("C:/Ryujinx"));
+        dirs.push(PathBuf::from("C:/Program Files/Ryujinx"));
+        dirs
+    }
+
+    fn arg_template(&self) -> &'static [&'static str] {
+        &["{fullscreen}", "{path}"]
+    }
+
+    fn fullscreen_args(&self) -> &'static [&'static str] {
+        &["--fullscreen"]
+    }
+}
+
+/// Game directories from a data dir's `Config.json`
+fn read_game_dirs(data_dir: &Path) -> Vec<PathBuf> {
+    let config_path = data_dir.join("Config.json");
+    let Ok(contents) = fs::read_to_string(&config_path) else {
+        return Vec::new();
+    };
+    match parse_game_dirs(&contents) {
+        Ok(dirs) => dirs,
+        Err(e) => {
+            log::warn!("Failed to parse {}: {}", config_path.display(), e);
+            Vec::new()
+        }
+    }
+}
+
+/// `Config.json` fields we read. Every config version so far, including
+/// the unversioned ones from before the `version` field, keeps game
+/// directories as a string list in `game_dirs`; other fields are ignored
+/// so newer versions keep working.
+#[derive(Deserialize)]
+struct Config {
+    #[serde(default)]
+    version: Option<u32>,
+    #[serde(default)]
+    game_dirs: Option<Vec<String>>,
+}
+
+/// Parse the game directories out of a `Config.json`
+fn parse_game_dirs(contents: &str) -> Result<Vec<PathBuf>, String> {
+    // Config.json written by Windows tools may start with a BOM
+    let contents = contents.trim_start_matches('\u{feff}');
+    let config: Config = serde_json::from_str(contents).map_err(|e| e.to_string())?;
+    log::debug!(
+        "Ryujinx config version {}",
+        config
+            .version
+            .map_or_else(|| "unversioned".to_string(), |v| v.to_string())
+    );
+
+    Ok(config
+        .game_dirs
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|dir| !dir.trim().is_empty())
+        .map(PathBuf::from)
+        .collect())
+}
+
+/// `games/<titleid>/gui/metadata.json`. Older versions kept playtime in
+/// seconds and the last played time as a local date string; newer ones
+/// use a .NET `TimeSpan` and a UTC timestamp.
+#[derive(Deserialize, Default)]
+struct Metadata {
+    #[serde(default)]
+    title: Option<String>,
+    #[serde(default)]
+    favorite: Option<bool>,
+    #[serde(default)]
+    timespan_played: Option<String>,
+    #[serde(default)]
+    time_played: Option<f64>,
+    #[serde(default)]
+    last_played_utc: Option<String>,
+    #[serde(default)]
+    last_played: Option<String>,
+}
+
+impl Metadata {
+    fn parse(contents: &str) -> Result<Self, String> {
+        serde_json::from_str(contents.trim_start_matches('\u{feff}')).map_err(|e| e.to_string())
+    }
+
+    fn playtime(&self) -> Option<Playtime> {
+        let seconds = self
+            .timespan_played
+            .as_deref()
+            .and_then(parse_timespan)
+            .or_else(|| {
+                self.time_played
+                    .filter(|s| s.is_finite() && *s >= 0.0)
+                    .map(|s| s as u64)
+            })?;
+        let last_played = self
+            .last_played_utc
+            .as_deref()
+            .or(self.last_played.as_deref())
+            .and_then(parse_datetime);
+        (seconds > 0 || last_played.is_some()).then_some(Playtime {
+            seconds,
+            last_played,
+        })
+    }
+}
+
+/// Read the per-title data under `games/<titleid>/`: name, favorite and
+/// playtime from `gui/metadata.json` and any cached icon, keyed by title ID
+fn read_title_metadata(data_dir: &Path) -> HashMap<String, TitleMetadata> {
+    let mut titles: HashMap<String, TitleMetadata> = HashMap::new();
+
+    let games_dir = data_dir.join("games");
+    if let Ok(entries) = fs::read_dir(&games_dir) {
+        for entry in entries.filter_map(|e| e.ok()) {
+            let Some(title_id) = entry.file_name().to_str().and_then(parse_title_id) else {
+                continue;
+            };
+
+            let metadata_path = entry.path().join("gui/metadata.json");
+            let metadata = match fs::read_to_string(&metadata_path) {
+                Ok(contents) => Metadata::parse(&contents).unwrap_or_else(|e| {
+                    log::warn!("Failed to parse {}: {}", metadata_path.display(), e);
+                    Metadata::default()
+                }),
+                Err(_) => Metadata::default(),
+            };
+            let title = titles.entry(title_id).or_default();
+            title.playtime = metadata.playtime();
+            title.favorite = metadata.favorite;
+            title.name = metadata.title.filter(|name| !name.is_empty());
+        }
+    }
+
+    // Icons cached in title ID named directories
+    let cache_dir = data_dir.join("bis/user/save/0000000000000000/0000000000000000/cache");
+    for root in [&games_dir, &cache_dir] {
+        for entry in WalkDir::new(root)
+            .max_depth(3)
+            .into_iter()
+            .filter_map(|e| e.ok())
+            .filter(|e| e.file_type().is_file())
+        {
+            let Some(mime_type) = image_mime_type(entry.path()) else {
+                continue;
+            };
+            let Some(title_id) = entry
+                .path()
+                .ancestors()
+                .skip(1)
+                .take_while(|p| *p != root.as_path())
+                .find_map(|p| p.file_name()?.to_str().and_then(parse_title_id))
+            else {
+                continue;
+            };
+
+            let title = titles.entry(title_id).or_default();
+            if title.icon.is_none() {
+                if let Ok(data) = fs::read(entry.path()) {
+                    title.icon = Some(format!(
+                        "data:{};base64,{}",
+                        mime_type,
+                        general_purpose::STANDARD.encode(&data)
+                    ));
+                }
+            }
+        }
+    }
+
+    titles
+}
+
+/// Existing `metadata.json` files for `title_id` in a data dir
+fn metadata_paths(data_dir: &Path, title_id: &str) -> Vec<PathBuf> {
+    let Ok(entries) = fs::read_dir(data_dir.join("games")) else {
+        return Vec::new();
+    };
+    entries
+        .filter_map(|e| e.ok())
+        .filter(|e| {
+            e.file_name()
+                .to_str()
+                .and_then(parse_title_id)
+                .is_some_and(|id| id.eq_ignore_ascii_case(title_id))
+        })
+        .map(|e| e.path().join("gui/metadata.json"))
+        .filter(|path| path.is_file())
+        .collect()
+}
+
+/// Set `favorite` in a `metadata.json`, keeping the other fields
+fn write_favorite(path: &Path, favorite: bool) -> Result<(), String> {
+    let contents = fs::read_to_string(path).map_err(|e| e.to_string())?;
+    let mut metadata: serde_json::Value =
+        serde_json::from_str(contents.trim_start_matches('\u{feff}'))
+            .map_err(|e| format!("Failed to parse {}: {}", path.display(), e))?;
+    let Some(fields) = metadata.as_object_mut() else {
+        return Err(format!("{} is not a JSON object", path.display()));
+    };
+    fields.insert("favorite".to_string(), favorite.into());
+
+    let json = serde_json::to_string_pretty(&metadata).map_err(|e| e.to_string())?;
+    let tmp_path = path.with_extension("json.tmp");
+    fs::write(&tmp_path, json).map_err(|e| e.to_string())?;
+    fs::rename(&tmp_path, path).map_err(|e| e.to_string())
+}
+
+/// Seconds in a .NET `TimeSpan` string: `[d.]hh:mm:ss[.fffffff]`
+fn parse_timespan(s: &str) -> Option<u64> {
+    let mut parts = s.trim().split(':');
+    let (hours, minutes, seconds) = (parts.next()?, parts.next()?, parts.next()?);
+    if parts.next().is_some() {
+        return None;
+    }
+    let (days, hours) = match hours.split_once('.') {
+        Some((days, hours)) => (days.parse::<u64>().ok()?, hours),
+        None => (0, hours),
+    };
+    let hours: u64 = hours.parse().ok()?;
+    let minutes: u64 = minutes.parse().ok()?;
+    let seconds: u64 = seconds.split('.').next()?.parse().ok()?;
+    Some(((days * 24 + hours) * 60 + minutes) * 60 + seconds)
+}
+
+/// Seconds since the Unix epoch for `YYYY-MM-DD[T ]hh:mm[:ss[.f]][Z|±hh:mm]`.
+/// Times without an offset are taken as UTC.
+fn parse_datetime(s: &str) -> Option<u64> {
+    let s = s.trim();
+    let (date, time) = s.split_at(s.find(['T', ' '])?);
+    let time = &time[1..];
+
+    let mut date = date.split('-');
+    let year: i64 = date.next()?.parse().ok()?;
+    let month: u32 = date.next()?.parse().ok()?;
+    let day: u32 = date.next()?.parse().ok()?;
+    if !(1..=12).contains(&month) || !(1..=31).contains(&day) {
+        return None;
+    }
+
+    let (time, offset) = if let Some(time) = time.strip_suffix('Z') {
+        (time, 0)
+    } else if let Some(i) = time.rfind(['+', '-']) {
+        let (time, offset) = time.split_at(i);
+        let sign = if offset.starts_with('-') { -1 } else { 1 };
+        let (hours, minutes) = offset[1..].split_once(':')?;
+        let offset = hours.parse::<i64>().ok()? * 3600 + minutes.parse::<i64>().ok()? * 60;
+        (time, sign * offset)
+    } else {
+        (time, 0)
+    };
+
+    let mut time = time.split(':');
+    let hours: i64 = time.next()?.parse().ok()?;
+    let minutes: i64 = time.next()?.parse().ok()?;
+    let seconds: i64 = match time.next() {
+        Some(seconds) => seconds.split('.').next()?.parse().ok()?,
+        None => 0,
+    };
+
+    let secs =
+        days_from_civil(year, month, day) * 86400 + hours * 3600 + minutes * 60 + seconds - offset;
+    u64::try_from(secs).ok()
+}
+
+/// Days since 1970-01-01 in the proleptic Gregorian calendar
+fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let month = i64::from(month);
+    let day_of_year = (153 * (month + if month > 2 { -3 } else { 9 }) + 2) / 5 + i64::from(day) - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146097 + day_of_era - 719468
+}
+
+/// Uppercased title ID if `name` is 16 hex digits
+fn parse_title_id(name: &str) -> Option<String> {
+    (name.len() == 16 && name.chars().all(|c| c.is_ascii_hexdigit())).then(|| name.to_uppercase())
+}
+
+fn image_mime_type(path: &Path) -> Option<&'static str> {
+    let ext = path.extension()?.to_str()?.to_ascii_lowercase();
+    match ext.as_str() {
+        "jpg" | "jpeg" => Some("image/jpeg"),
+        "png" => Some("image/png"),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_config_versions() {
+        macro_rules! fixture {
+            ($name:literal) => {
+                parse_game_dirs(include_str!(concat!(
+                    "../../tests/fixtures/ryujinx/",
+                    $name
+                )))
+            };
+        }
+
+        assert_eq!(
+            fixture!("Config.unversioned.json").unwrap(),
+            vec![PathBuf::from("/home/user/Games/Switch")]
+        );
+        assert_eq!(
+            fixture!("Config.v10.json").unwrap(),
+            vec![
+                PathBuf::from("C:\\Users\\user\\Games\\Switch"),
+                PathBuf::from("D:\\Switch")
+            ]
+        );
+        // autoload_dirs hold updates and DLC, not games
+        assert_eq!(
+            fixture!("Config.v68.json").unwrap(),
+            vec![
+                PathBuf::from("/home/user/Games/Switch"),
+                PathBuf::from("/mnt/storage/Switch")
+            ]
+        );
+        assert!(fixture!("Config.empty.json").unwrap().is_empty());
+        assert!(parse_game_dirs("\u{feff}{\"game_dirs\": [\"/games\"]}").is_ok());
+        assert!(parse_game_dirs("{\"game_dirs\": \"/games\"}").is_err());
+    }
+
+    #[test]
+    fn test_find_portable_install() {
+        let home = tempfile::tempdir().unwrap();
+        let portable = home
+            .path()
+            .join("Downloads/ryujinx-1.1.1376-win_x64/publish/portable");
+        fs::create_dir_all(&portable).unwrap();
+        fs::write(portable.join("Config.json"), "{}").unwrap();
+
+        let locations = Locations::find(home.path(), true);
+        assert!(RYUJINX.data_dirs(&locations).contains(&portable));
+        assert!(RYUJINX
+            .key_dirs(&locations)
+            .contains(&portable.join("system")));
+    }
+
+    #[test]
+    fn test_title_metadata() {
+        let home = tempfile::tempdir().unwrap();
+        let title_dir = home.path().join(".config/Ryujinx/games/0100abcd00000800");
+        fs::create_dir_all(title_dir.join("gui")).unwrap();
+        fs::write(
+            title_dir.join("gui/metadata.json"),
+            r#"{"title": "Ryujinx Name", "favorite": false}"#,
+        )
+        .unwrap();
+        fs::write(title_dir.join("icon.png"), b"png").unwrap();
+
+        let titles = RYUJINX.title_metadata(&Locations::find(home.path(), true));
+        let title = &titles["0100ABCD00000800"];
+        assert_eq!(title.name.as_deref(), Some("Ryujinx Name"));
+        assert_eq!(title.icon.as_deref(), Some("data:image/png;base64,cG5n"));
+        assert_eq!(title.favorite, Some(false));
+        assert_eq!(title.playtime, None);
+    }
+
+    #[test]
+    fn test_metadata_playtime() {
+        let current = Metadata::parse(
+            r#"{
+                "title": "Game",
+                "favorite": true,
+                "timespan_played": "1.02:03:04.5000000",
+                "last_played_utc": "2024-03-01T12:00:00.1234567Z"
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(
+            current.playtime(),
+            Some(Playtime {
+                seconds: 93784,
+                last_played: Some(1709294400),
+            })
+        );
+
+        let old = Metadata::parse(
+            r#"{"title": "Game", "favorite": false, "time_played": 3600.5, "last_played": "2021-11-14 20:11"}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            old.playtime(),
+            Some(Playtime {
+                seconds: 3600,
+                last_played: Some(1636920660),
+            })
+        );
+
+        let never = Metadata::parse(
+            r#"{"title": "Game", "timespan_played": "00:00:00", "last_played": "Never"}"#,
+        )
+        .unwrap();
+        assert_eq!(never.playtime(), None);
+    }
+
+    #[test]
+    fn test_parse_datetime() {
+        assert_eq!(parse_datetime("1970-01-01T00:00:00Z"), Some(0));
+        assert_eq!(parse_datetime("2000-02-29T00:00:00Z"), Some(951782400));
+        assert_eq!(
+            parse_datetime("2024-03-01T14:00:00+02:00"),
+            parse_datetime("2024-03-01T12:00:00Z")
+        );
+        assert_eq!(parse_datetime("1969-12-31T23:59:59Z"), None);
+        assert_eq!(parse_datetime("2024-13-01T00:00:00Z"), None);
+        assert_eq!(parse_timespan("-00:00:01"), None);
+    }
+
+    #[test]
+    fn test_set_favorite() {
+        let home = tempfile::tempdir().unwrap();
+        let metadata = home
+            .path()
+            .join(".config/Ryujinx/games/0100abcd00000800/gui/metadata.json");
+        fs::create_dir_all(metadata.parent().unwrap()).unwrap();
+        fs::write(
+            &metadata,
+            r#"{"title": "Game", "favorite": false, "timespan_played": "00:10:00"}"#,
+        )
+        .unwrap();
+
+        let locations = Locations::find(home.path(), true);
+        assert!(RYUJINX
+            .set_favorite(&locations, "0100ABCD00000800", true)
+            .unwrap());
+        let written: serde_json::Value =
+            serde_json::from_str(&fs::read_to_string(&metadata).unwrap()).unwrap();
+        assert_eq!(written["favorite"], true);
+        assert_eq!(written["timespan_played"], "00:10:00");
+
+        // Titles Ryujinx has no metadata for are left alone
+        assert!(!RYUJINX
+            .set_favorite(&locations, "0100000000010000", true)
+            .unwrap());
+    }
+}
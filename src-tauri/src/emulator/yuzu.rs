@@ -0,0 +1,395 @@
+//! yuzu and the forks that kept its data layout and command line
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use super::{Emulator, GameDir, Locations, Playtime, TitleMetadata};
+use crate::qsettings::{QSettings, Value};
+
+/// An emulator from the yuzu family. They differ only in names: each keeps
+/// a `qt-config.ini` and `keys`/`load`/`nand` folders under its own data
+/// directory, and boots games with `-f -g <path>`.
+#[derive(Debug)]
+pub struct YuzuFamily {
+    id: &'static str,
+    name: &'static str,
+    /// Folder name under the platform config/data directories
+    dir_name: &'static str,
+    executable_names: &'static [&'static str],
+}
+
+pub static YUZU: YuzuFamily = YuzuFamily {
+    id: "yuzu",
+    name: "yuzu",
+    dir_name: "yuzu",
+    executable_names: &["yuzu"],
+};
+
+pub static SUYU: YuzuFamily = YuzuFamily {
+    id: "suyu",
+    name: "Suyu",
+    dir_name: "suyu",
+    executable_names: &["suyu"],
+};
+
+pub static SUDACHI: YuzuFamily = YuzuFamily {
+    id: "sudachi",
+    name: "Sudachi",
+    dir_name: "sudachi",
+    executable_names: &["sudachi"],
+};
+
+pub static CITRON: YuzuFamily = YuzuFamily {
+    id: "citron",
+    name: "Citron",
+    dir_name: "citron",
+    executable_names: &["citron"],
+};
+
+pub static EDEN: YuzuFamily = YuzuFamily {
+    id: "eden",
+    name: "Eden",
+    dir_name: "eden",
+    executable_names: &["eden"],
+};
+
+// Torzu ships its binary under yuzu's name
+pub static TORZU: YuzuFamily = YuzuFamily {
+    id: "torzu",
+    name: "Torzu",
+    dir_name: "torzu",
+    executable_names: &["torzu", "yuzu"],
+};
+
+impl YuzuFamily {
+    /// Possible `qt-config.ini` locations (standard + portable installs)
+    fn config_paths(&self, locations: &Locations) -> Vec<PathBuf> {
+        let home_dir = &locations.home_dir;
+        let mut config_paths = vec![
+            home_dir
+                .join(".config")
+                .join(self.dir_name)
+                .join("qt-config.ini"),
+            home_dir
+                .join(".local/share")
+                .join(self.dir_name)
+                .join("qt-config.ini"),
+            home_dir
+                .join("AppData/Roaming")
+                .join(self.dir_name)
+                .join("config/qt-config.ini"),
+            home_dir
+                .join("AppData/Roaming")
+                .join(self.dir_name)
+                .join("qt-config.ini"),
+            home_dir
+                .join("Library/Application Support")
+                .join(self.dir_name)
+                .join("qt-config.ini"),
+        ];
+
+        // Portable installs found in Downloads
+        config_paths.extend(locations.portable_files("qt-config.ini", self.id));
+
+        config_paths
+    }
+}
+
+impl Emulator for YuzuFamily {
+    fn id(&self) -> &'static str {
+        self.id
+    }
+
+    fn name(&self) -> &'static str {
+        self.name
+    }
+
+    fn data_dirs(&self, locations: &Locations) -> Vec<PathBuf> {
+        let home_dir = &locations.home_dir;
+        let mut dirs = vec![
+            home_dir.join(".local/share").join(self.dir_name),
+            home_dir.join("AppData/Roaming").join(self.dir_name),
+            home_dir
+                .join("Library/Application Support")
+                .join(self.dir_name),
+        ];
+
+        // Portable installs keep config and data together in `user/`
+        for config_path in self.config_paths(locations) {
+            let Some(config_dir) = config_path.parent() else {
+                continue;
+            };
+            if config_dir.file_name() == Some("config".as_ref()) {
+                if let Some(user_dir) = config_dir.parent() {
+                    dirs.push(user_dir.to_path_buf());
+                }
+            }
+        }
+
+        dirs.sort();
+        dirs.dedup();
+        dirs
+    }
+
+    fn game_dirs(&self, locations: &Locations) -> Vec<GameDir> {
+        let mut game_dirs: Vec<GameDir> = self
+            .data_dirs(locations)
+            .into_iter()
+            .flat_map(|dir| [dir.join("load"), dir.join("nand/user/Contents/registered")])
+            .map(GameDir::new)
+            .collect();
+
+        // Add custom game directories from the config
+        for config_path in self.config_paths(locations) {
+            if let Ok(contents) = fs::read_to_string(&config_path) {
+                game_dirs.extend(parse_game_dirs(&contents));
+            }
+        }
+
+        game_dirs
+    }
+
+    fn default_game_dirs(&self, locations: &Locations) -> Vec<GameDir> {
+        if self.id != YUZU.id {
+            return Vec::new();
+        }
+
+        // Common game storage locations, attributed to yuzu since before
+        // forks existed
+        [
+            "Documents/Yuzu/games",
+            "Games/Switch",
+            "Games/Yuzu",
+            "Downloads",
+            "Downloads/Switch",
+        ]
+        .into_iter()
+        .map(|dir| GameDir::new(locations.home_dir.join(dir)))
+        .collect()
+    }
+
+    /// Favorites from the config and play time from the `play_time`
+    /// folders, summed over user profiles
+    fn title_metadata(&self, locations: &Locations) -> HashMap<String, TitleMetadata> {
+        let mut titles: HashMap<String, TitleMetadata> = HashMap::new();
+
+        for config_path in self.config_paths(locations) {
+            if let Ok(contents) = fs::read_to_string(&config_path) {
+                for title_id in parse_favorites(&contents) {
+                    titles.entry(title_id).or_default().favorite = Some(true);
+                }
+            }
+        }
+
+        for data_dir in self.data_dirs(locations) {
+            for (title_id, seconds) in read_play_time(&data_dir.join("play_time")) {
+                titles
+                    .entry(title_id)
+                    .or_default()
+                    .playtime
+                    .get_or_insert_with(Playtime::default)
+                    .seconds += seconds;
+            }
+        }
+
+        titles
+    }
+
+    fn executable_names(&self) -> &'static [&'static str] {
+        self.executable_names
+    }
+
+    fn install_dirs(&self) -> Vec<PathBuf> {
+        let mut dirs = Vec::new();
+        if let Some(local_app_data) = dirs::data_local_dir() {
+            dirs.push(local_app_data.join(self.dir_name));
+            dirs.push(local_app_data.join("Programs").join(self.dir_name));
+        }
+        dirs.push(PathBuf::from("C:/Program Files").join(self.dir_name));
+        dirs.push(PathBuf::from("C:/Program Files (x86)").join(self.dir_name));
+        dirs
+    }
+
+    fn arg_template(&self) -> &'static [&'static str] {
+        &["{fullscreen}", "-g", "{path}"]
+    }
+
+    fn fullscreen_args(&self) -> &'static [&'static str] {
+        &["-f"]
+    }
+
+    fn fullscreen_by_default(&self) -> bool {
+        true
+    }
+}
+
+/// Parse the `Paths\gamedirs` array in the `[UI]` section of a
+/// `qt-config.ini`. The built-in SDMC and NAND entries are skipped.
+fn parse_game_dirs(contents: &str) -> Vec<GameDir> {
+    let settings = QSettings::parse(contents);
+    settings
+        .array("UI", "Paths/gamedirs")
+        .into_iter()
+        .filter_map(|entry| {
+            let path = entry.get("path")?.text()?;
+            if path.is_empty() || matches!(path.as_str(), "SDMC" | "UserNAND" | "SysNAND") {
+                return None;
+            }
+            let deep_scan = entry
+                .get("deep_scan")
+                .and_then(Value::as_bool)
+                .unwrap_or(false);
+            Some(GameDir::configured(PathBuf::from(path), deep_scan))
+        })
+        .collect()
+}
+
+/// Title IDs in the `UIGameList\favorites` array of a `qt-config.ini`
+fn parse_favorites(contents: &str) -> Vec<String> {
+    QSettings::parse(contents)
+        .array("UI", "UIGameList/favorites")
+        .into_iter()
+        .filter_map(|entry| {
+            // Stored as a decimal u64
+            let program_id: u64 = entry.get("program_id")?.text()?.parse().ok()?;
+            (program_id != 0).then(|| format!("{:016X}", program_id))
+        })
+        .collect()
+}
+
+/// Play time per title ID from the `<profile UUID>.bin` files in `dir`.
+/// Each file is a list of (program ID, seconds) pairs of little-endian
+/// u64s.
+fn read_play_time(dir: &Path) -> Vec<(String, u64)> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+
+    let mut play_time = Vec::new();
+    for path in entries.filter_map(|e| e.ok()).map(|e| e.path()) {
+        if path.extension() != Some("bin".as_ref()) {
+            continue;
+        }
+        let Ok(data) = fs::read(&path) else {
+            continue;
+        };
+        if data.len() % 16 != 0 {
+            log::warn!("Ignoring truncated play time file {}", path.display());
+            continue;
+        }
+        for record in data.chunks_exact(16) {
+            let program_id = u64::from_le_bytes(record[..8].try_into().unwrap());
+            let seconds = u64::from_le_bytes(record[8..].try_into().unwrap());
+            if program_id != 0 {
+                play_time.push((format!("{:016X}", program_id), seconds));
+            }
+        }
+    }
+    play_time
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::emulator::LaunchTarget;
+
+    #[test]
+    fn test_parse_game_dirs() {
+        let dirs = parse_game_dirs(include_str!("../../tests/fixtures/yuzu/qt-config.ini"));
+        assert_eq!(
+            dirs,
+            vec![
+                GameDir::configured(PathBuf::from("/home/user/Games/Switch"), true),
+                GameDir::configured(PathBuf::from("/mnt/games/Switch Dumps"), false),
+                GameDir::configured(PathBuf::from("C:/Users/user/Games, Switch"), false),
+            ]
+        );
+        assert_eq!(dirs[0].max_depth, usize::MAX);
+        assert_eq!(dirs[1].max_depth, 1);
+    }
+
+    #[test]
+    fn test_parse_favorites() {
+        let favorites = parse_favorites(include_str!("../../tests/fixtures/yuzu/qt-config.ini"));
+        assert_eq!(favorites, vec!["0100000000010000", "0100800000000000"]);
+    }
+
+    #[test]
+    fn test_title_metadata() {
+        let home = tempfile::tempdir().unwrap();
+        let data_dir = home.path().join(".local/share/yuzu");
+        fs::create_dir_all(data_dir.join("play_time")).unwrap();
+        let record = |program_id: u64, seconds: u64| {
+            [program_id.to_le_bytes(), seconds.to_le_bytes()].concat()
+        };
+        // Two user profiles played the same title
+        fs::write(
+            data_dir.join("play_time/00000000000000000000000000000001.bin"),
+            [
+                record(0x0100000000010000, 3600),
+                record(0x0100ABCD00000800, 60),
+            ]
+            .concat(),
+        )
+        .unwrap();
+        fs::write(
+            data_dir.join("play_time/00000000000000000000000000000002.bin"),
+            record(0x0100000000010000, 1800),
+        )
+        .unwrap();
+        fs::create_dir_all(home.path().join(".config/yuzu")).unwrap();
+        fs::write(
+            home.path().join(".config/yuzu/qt-config.ini"),
+            "[UI]\nUIGameList\\favorites\\1\\program_id=72057594037993472\nUIGameList\\favorites\\size=1\n",
+        )
+        .unwrap();
+
+        let locations = Locations::find(home.path(), true);
+        let titles = YUZU.title_metadata(&locations);
+        let title = &titles["0100000000010000"];
+        assert_eq!(title.favorite, Some(true));
+        assert_eq!(title.playtime.unwrap().seconds, 5400);
+        assert_eq!(titles["0100ABCD00000800"].favorite, None);
+
+        // Forks have their own data
+        assert!(SUYU.title_metadata(&locations).is_empty());
+    }
+
+    #[test]
+    fn test_fork_portable_install() {
+        let home = tempfile::tempdir().unwrap();
+        let user_dir = home.path().join("Downloads/sudachi-windows/user");
+        fs::create_dir_all(user_dir.join("config")).unwrap();
+        fs::write(
+            user_dir.join("config/qt-config.ini"),
+            "[UI]\nPaths\\gamedirs\\1\\path=/games\nPaths\\gamedirs\\size=1\n",
+        )
+        .unwrap();
+
+        let locations = Locations::find(home.path(), true);
+        assert!(SUDACHI.data_dirs(&locations).contains(&user_dir));
+        assert!(SUDACHI
+            .key_dirs(&locations)
+            .contains(&user_dir.join("keys")));
+        let game_dirs = SUDACHI.game_dirs(&locations);
+        assert!(game_dirs.contains(&GameDir::configured(PathBuf::from("/games"), false)));
+        assert!(game_dirs.contains(&GameDir::new(user_dir.join("load"))));
+
+        // Other forks don't pick up Sudachi's portable install
+        assert!(!CITRON.data_dirs(&locations).contains(&user_dir));
+    }
+
+    #[test]
+    fn test_launch_args() {
+        let profile = EDEN.default_profile(None);
+        let target = LaunchTarget {
+            path: Path::new("/games/game.nsp"),
+            title_id: None,
+        };
+        assert_eq!(
+            profile.expand_args(&target).unwrap(),
+            vec!["-f", "-g", "/games/game.nsp"]
+        );
+    }
+}
@@ -0,0 +1,100 @@
+//! Readers for Switch container and metadata formats
+
+use std::fmt;
+use std::io;
+
+use crate::keys::KeyError;
+
+pub mod nacp;
+pub mod nca;
+pub mod nro;
+pub mod pfs0;
+pub mod romfs;
+pub mod xci;
+
+/// Error returned by the format readers
+#[derive(Debug)]
+pub enum FormatError {
+    Io(io::Error),
+    /// The magic at the start of a structure did not match
+    BadMagic {
+        expected: &'static str,
+        found: [u8; 4],
+    },
+    /// The structure points past the end of the file
+    Truncated {
+        needed: u64,
+        available: u64,
+    },
+    /// The structure is internally inconsistent
+    Invalid(String),
+    /// Decrypting the structure needs keys we do not have
+    Keys(KeyError),
+}
+
+impl fmt::Display for FormatError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FormatError::Io(e) => write!(f, "I/O error: {}", e),
+            FormatError::BadMagic { expected, found } => write!(
+                f,
+                "Bad magic: expected {}, found {:?}",
+                expected,
+                String::from_utf8_lossy(found)
+            ),
+            FormatError::Truncated { needed, available } => write!(
+                f,
+                "File is truncated: needs {} bytes, only {} available",
+                needed, available
+            ),
+            FormatError::Invalid(msg) => write!(f, "Invalid data: {}", msg),
+            FormatError::Keys(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl std::error::Error for FormatError {}
+
+impl From<io::Error> for FormatError {
+    fn from(e: io::Error) -> Self {
+        FormatError::Io(e)
+    }
+}
+
+impl From<KeyError> for FormatError {
+    fn from(e: KeyError) -> Self {
+        FormatError::Keys(e)
+    }
+}
+
+impl From<FormatError> for String {
+    fn from(e: FormatError) -> Self {
+        e.to_string()
+    }
+}
+
+pub(crate) fn read_u32(buf: &[u8], offset: usize) -> u32 {
+    u32::from_le_bytes(buf[offset..offset + 4].try_into().unwrap())
+}
+
+pub(crate) fn read_u64(buf: &[u8], offset: usize) -> u64 {
+    u64::from_le_bytes(buf[offset..offset + 8].try_into().unwrap())
+}
+
+/// Read a NUL-terminated string starting at `offset`
+pub(crate) fn read_cstr(buf: &[u8], offset: usize) -> Option<String> {
+    let rest = buf.get(offset..)?;
+    let end = rest.iter().position(|&b| b == 0).unwrap_or(rest.len());
+    Some(String::from_utf8_lossy(&rest[..end]).into_owned())
+}
+
+/// Find a 16 hex digit title ID inside a file name like
+/// `0100000000010000000000000000000a.tik`
+pub fn title_id_from_name(name: &str) -> Option<String> {
+    for word in name.split(|c: char| !c.is_ascii_alphanumeric()) {
+        if (word.len() == 16 || word.len() == 32) && word.chars().all(|c| c.is_ascii_hexdigit()) {
+            return Some(word[..16].to_ascii_uppercase());
+        }
+    }
+    None
+}
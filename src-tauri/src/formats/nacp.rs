@@ -0,0 +1,178 @@
+//! NACP application control property, the metadata block shipped with
+//! every title (inside the control NCA) and homebrew (inside the NRO ASET)
+
+use super::{read_cstr, read_u32, FormatError};
+
+pub const NACP_SIZE: usize = 0x4000;
+
+const TITLE_ENTRY_SIZE: usize = 0x300;
+const TITLE_NAME_SIZE: usize = 0x200;
+const SUPPORTED_LANGUAGES_OFFSET: usize = 0x302C;
+const RATING_AGE_OFFSET: usize = 0x3040;
+const DISPLAY_VERSION_OFFSET: usize = 0x3060;
+const DISPLAY_VERSION_SIZE: usize = 0x10;
+
+/// Languages of the 16 title entries, in NACP order
+pub const LANGUAGES: [&str; 16] = [
+    "AmericanEnglish",
+    "BritishEnglish",
+    "Japanese",
+    "French",
+    "German",
+    "LatinAmericanSpanish",
+    "Spanish",
+    "Italian",
+    "Dutch",
+    "CanadianFrench",
+    "Portuguese",
+    "Russian",
+    "Korean",
+    "TraditionalChinese",
+    "SimplifiedChinese",
+    "BrazilianPortuguese",
+];
+
+/// Rating organizations of the age rating table, in NACP order
+pub const RATING_ORGANIZATIONS: [&str; 13] = [
+    "CERO",
+    "GRACGCRB",
+    "GSRMR",
+    "ESRB",
+    "ClassInd",
+    "USK",
+    "PEGI",
+    "PEGIPortugal",
+    "PEGIBBFC",
+    "Russian",
+    "ACB",
+    "OFLC",
+    "IARCGeneric",
+];
+
+/// Localized name and publisher
+#[derive(Debug, Clone, PartialEq)]
+pub struct NacpTitle {
+    pub language: &'static str,
+    pub name: String,
+    pub publisher: String,
+}
+
+/// Parsed NACP fields
+#[derive(Debug, Clone)]
+pub struct Nacp {
+    /// Non-empty title entries in NACP language order
+    pub titles: Vec<NacpTitle>,
+    pub display_version: String,
+    /// Languages the application supports
+    pub supported_languages: Vec<&'static str>,
+    /// Minimum age per rating organization, for the rated ones
+    pub age_ratings: Vec<(&'static str, u8)>,
+}
+
+impl Nacp {
+    pub fn parse(data: &[u8]) -> Result<Self, FormatError> {
+        if data.len() < NACP_SIZE {
+            return Err(FormatError::Truncated {
+                needed: NACP_SIZE as u64,
+                available: data.len() as u64,
+            });
+        }
+
+        let mut titles = Vec::new();
+        for (i, language) in LANGUAGES.iter().enumerate() {
+            let entry = &data[i * TITLE_ENTRY_SIZE..(i + 1) * TITLE_ENTRY_SIZE];
+            let name = fixed_str(&entry[..TITLE_NAME_SIZE]);
+            if name.is_empty() {
+                continue;
+            }
+            titles.push(NacpTitle {
+                language,
+                name,
+                publisher: fixed_str(&entry[TITLE_NAME_SIZE..]),
+            });
+        }
+
+        let display_version =
+            fixed_str(&data[DISPLAY_VERSION_OFFSET..DISPLAY_VERSION_OFFSET + DISPLAY_VERSION_SIZE]);
+
+        let language_flags = read_u32(data, SUPPORTED_LANGUAGES_OFFSET);
+        let supported_languages = LANGUAGES
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| language_flags & (1 << i) != 0)
+            .map(|(_, language)| *language)
+            .collect();
+
+        // Unrated organizations are stored as -1
+        let age_ratings = RATING_ORGANIZATIONS
+            .iter()
+            .enumerate()
+            .filter_map(|(i, org)| {
+                let age = data[RATING_AGE_OFFSET + i] as i8;
+                (age >= 0).then_some((*org, age as u8))
+            })
+            .collect();
+
+        Ok(Nacp {
+            titles,
+            display_version,
+            supported_languages,
+            age_ratings,
+        })
+    }
+}
+
+/// Read a NUL-padded fixed size string field
+fn fixed_str(field: &[u8]) -> String {
+    read_cstr(field, 0).unwrap_or_default().trim().to_string()
+}
+
+/// Build a NACP with a single English entry for tests
+#[cfg(test)]
+pub(crate) fn build_nacp(name: &str, publisher: &str, version: &str) -> Vec<u8> {
+    let mut data = vec![0u8; NACP_SIZE];
+    data[RATING_AGE_OFFSET..RATING_AGE_OFFSET + 0x20].fill(0xFF);
+    data[..name.len()].copy_from_slice(name.as_bytes());
+    data[TITLE_NAME_SIZE..TITLE_NAME_SIZE + publisher.len()].copy_from_slice(publisher.as_bytes());
+    data[DISPLAY_VERSION_OFFSET..DISPLAY_VERSION_OFFSET + version.len()]
+        .copy_from_slice(version.as_bytes());
+    data
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_title_and_version() {
+        let mut data = build_nacp("Checkpoint", "Bernardo Giordano", "3.8.0");
+        // Japanese entry only
+        data[2 * TITLE_ENTRY_SIZE..2 * TITLE_ENTRY_SIZE + 3].copy_from_slice(b"JPN");
+        // English and Japanese, rated ESRB 10+ and PEGI 7
+        data[SUPPORTED_LANGUAGES_OFFSET] = 0b101;
+        data[RATING_AGE_OFFSET + 3] = 10;
+        data[RATING_AGE_OFFSET + 6] = 7;
+
+        let nacp = Nacp::parse(&data).unwrap();
+        assert_eq!(nacp.titles.len(), 2);
+        assert_eq!(nacp.titles[1].language, "Japanese");
+
+        let title = &nacp.titles[0];
+        assert_eq!(title.name, "Checkpoint");
+        assert_eq!(title.publisher, "Bernardo Giordano");
+        assert_eq!(nacp.display_version, "3.8.0");
+        assert_eq!(
+            nacp.supported_languages,
+            vec!["AmericanEnglish", "Japanese"]
+        );
+        assert_eq!(nacp.age_ratings, vec![("ESRB", 10), ("PEGI", 7)]);
+    }
+
+    #[test]
+    fn test_short_nacp() {
+        assert!(matches!(
+            Nacp::parse(&[0u8; 0x100]),
+            Err(FormatError::Truncated { .. })
+        ));
+    }
+}
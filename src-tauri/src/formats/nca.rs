@@ -0,0 +1,691 @@
+//! NCA content archives: header decryption and section table
+
+use aes::cipher::{
+    generic_array::GenericArray, BlockDecrypt, BlockEncrypt, KeyInit, KeyIvInit, StreamCipher,
+};
+use aes::Aes128;
+use serde::Serialize;
+use std::io::{self, Read, Seek, SeekFrom};
+
+use super::{read_u32, read_u64, FormatError};
+use crate::keys::{Key128, KeyAreaKeyIndex, KeyError, KeySet};
+
+type Aes128Ctr = ctr::Ctr128BE<Aes128>;
+
+pub const NCA_HEADER_SIZE: usize = 0xC00;
+const SECTOR_SIZE: usize = 0x200;
+const MEDIA_UNIT: u64 = 0x200;
+const FS_HEADER_OFFSET: usize = 0x400;
+const FS_HEADER_SIZE: usize = 0x200;
+const SECTION_COUNT: usize = 4;
+
+/// What an NCA contains
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub enum ContentType {
+    Program,
+    Meta,
+    Control,
+    Manual,
+    Data,
+    PublicData,
+}
+
+/// Filesystem stored in an NCA section
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub enum FsType {
+    RomFs,
+    PartitionFs,
+}
+
+/// How an NCA section is encrypted
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub enum EncryptionType {
+    None,
+    AesXts,
+    AesCtr,
+    AesCtrEx,
+    Other(u8),
+}
+
+/// One entry of the NCA section table with its filesystem header
+#[derive(Debug, Clone, Serialize)]
+pub struct NcaSection {
+    pub index: usize,
+    /// Offset of the section from the start of the NCA
+    pub offset: u64,
+    pub size: u64,
+    pub fs_type: FsType,
+    pub encryption: EncryptionType,
+    /// Offset of the filesystem (past the hash tree) from the section start
+    pub fs_offset: u64,
+    pub fs_size: u64,
+    /// Upper half of the AES-CTR counter
+    pub ctr: u64,
+}
+
+/// Decrypted NCA header
+#[derive(Debug, Clone, Serialize)]
+pub struct NcaHeader {
+    pub content_type: ContentType,
+    pub program_id: u64,
+    /// Effective key generation (0 for the first firmware keys)
+    pub key_generation: u8,
+    pub key_area_key_index: KeyAreaKeyIndex,
+    pub rights_id: [u8; 16],
+    pub content_size: u64,
+    pub sections: Vec<NcaSection>,
+    /// Encrypted key area (XTS key pair, CTR key, unused)
+    #[serde(skip)]
+    pub key_area: [Key128; 4],
+}
+
+impl NcaHeader {
+    /// Read and decrypt the header of the NCA starting at `offset`
+    pub fn read<R: Read + Seek>(
+        reader: &mut R,
+        offset: u64,
+        keys: &KeySet,
+    ) -> Result<Self, FormatError> {
+        let mut data = vec![0u8; NCA_HEADER_SIZE];
+        reader.seek(SeekFrom::Start(offset))?;
+        reader.read_exact(&mut data)?;
+        Self::parse(&mut data, keys)
+    }
+
+    /// Decrypt `data` (the first 0xC00 bytes of an NCA) in place and parse it
+    pub fn parse(data: &mut [u8], keys: &KeySet) -> Result<Self, FormatError> {
+        if data.len() < NCA_HEADER_SIZE {
+            return Err(FormatError::Truncated {
+                needed: NCA_HEADER_SIZE as u64,
+                available: data.len() as u64,
+            });
+        }
+        let header_key = keys.header_key()?;
+
+        xts_decrypt(header_key, &mut data[..FS_HEADER_OFFSET], 0);
+        match &data[0x200..0x204] {
+            b"NCA3" => xts_decrypt(header_key, &mut data[FS_HEADER_OFFSET..NCA_HEADER_SIZE], 2),
+            // NCA2 encrypts every filesystem header as sector 0
+            b"NCA2" => {
+                for fs_header in data[FS_HEADER_OFFSET..NCA_HEADER_SIZE].chunks_mut(FS_HEADER_SIZE)
+                {
+                    xts_decrypt(header_key, fs_header, 0);
+                }
+            }
+            found => {
+                return Err(FormatError::BadMagic {
+                    expected: "NCA3",
+                    found: found.try_into().unwrap(),
+                })
+            }
+        }
+
+        let content_type = match data[0x205] {
+            0 => ContentType::Program,
+            1 => ContentType::Meta,
+            2 => ContentType::Control,
+            3 => ContentType::Manual,
+            4 => ContentType::Data,
+            5 => ContentType::PublicData,
+            other => {
+                return Err(FormatError::Invalid(format!(
+                    "Unknown NCA content type {}",
+                    other
+                )))
+            }
+        };
+
+        let key_area_key_index = match data[0x207] {
+            0 => KeyAreaKeyIndex::Application,
+            1 => KeyAreaKeyIndex::Ocean,
+            2 => KeyAreaKeyIndex::System,
+            other => {
+                return Err(FormatError::Invalid(format!(
+                    "Unknown key area key index {}",
+                    other
+                )))
+            }
+        };
+
+        // Generations 0 and 1 both mean the first master key
+        let key_generation = data[0x206].max(data[0x220]).saturating_sub(1);
+
+        let mut sections = Vec::new();
+        for index in 0..SECTION_COUNT {
+            let entry = &data[0x240 + index * 0x10..];
+            let start = read_u32(entry, 0) as u64 * MEDIA_UNIT;
+            let end = read_u32(entry, 4) as u64 * MEDIA_UNIT;
+            if end <= start {
+                continue;
+            }
+
+            let fs_header = &data[FS_HEADER_OFFSET + index * FS_HEADER_SIZE..][..FS_HEADER_SIZE];
+            let fs_type = match fs_header[2] {
+                0 => FsType::RomFs,
+                1 => FsType::PartitionFs,
+                other => {
+                    return Err(FormatError::Invalid(format!(
+                        "Unknown filesystem type {} in section {}",
+                        other, index
+                    )))
+                }
+            };
+            let encryption = match fs_header[4] {
+                1 => EncryptionType::None,
+                2 => EncryptionType::AesXts,
+                3 => EncryptionType::AesCtr,
+                4 => EncryptionType::AesCtrEx,
+                other => EncryptionType::Other(other),
+            };
+
+            // The filesystem itself is the last layer of the hash tree
+            let (fs_offset, fs_size) = match fs_type {
+                // HierarchicalSha256: layer count at 0x2C, regions from 0x30
+                FsType::PartitionFs => {
+                    let layers = (read_u32(fs_header, 0x2C) as usize).clamp(1, 5);
+                    let region = 0x30 + (layers - 1) * 0x10;
+                    (read_u64(fs_header, region), read_u64(fs_header, region + 8))
+                }
+                // IVFC: level 6 holds the data
+                FsType::RomFs => (read_u64(fs_header, 0x90), read_u64(fs_header, 0x98)),
+            };
+            let size = end - start;
+            if fs_offset
+                .checked_add(fs_size)
+                .filter(|&fs_end| fs_end <= size)
+                .is_none()
+            {
+                return Err(FormatError::Invalid(format!(
+                    "Filesystem of section {} is out of range",
+                    index
+                )));
+            }
+
+            sections.push(NcaSection {
+                index,
+                offset: start,
+                size,
+                fs_type,
+                encryption,
+                fs_offset,
+                fs_size,
+                ctr: read_u64(fs_header, 0x140),
+            });
+        }
+
+        let mut key_area = [[0u8; 16]; 4];
+        for (i, key) in key_area.iter_mut().enumerate() {
+            key.copy_from_slice(&data[0x300 + i * 0x10..][..0x10]);
+        }
+
+        Ok(NcaHeader {
+            content_type,
+            program_id: read_u64(data, 0x210),
+            key_generation,
+            key_area_key_index,
+            rights_id: data[0x230..0x240].try_into().unwrap(),
+            content_size: read_u64(data, 0x208),
+            sections,
+            key_area,
+        })
+    }
+
+    /// Program ID formatted as a title ID
+    pub fn title_id(&self) -> String {
+        format!("{:016X}", self.program_id)
+    }
+
+    /// The AES-CTR key for the NCA's sections: the ticket's title key if
+    /// the NCA has a rights ID, otherwise the key from its key area
+    pub fn content_key(&self, keys: &KeySet) -> Result<Key128, KeyError> {
+        let generation = self.key_generation as usize;
+
+        if self.rights_id.iter().any(|&b| b != 0) {
+            let title_key = keys.title_keys.get(&self.rights_id).ok_or_else(|| {
+                KeyError::Missing(format!("title key for rights ID {}", hex(&self.rights_id)))
+            })?;
+            let titlekek = keys
+                .titlekeks
+                .get(generation)
+                .copied()
+                .flatten()
+                .ok_or_else(|| KeyError::Missing(format!("titlekek_{:02x}", generation)))?;
+            return Ok(ecb_decrypt(&titlekek, title_key));
+        }
+
+        let key_area_key = keys.key_area_keys[self.key_area_key_index as usize]
+            .get(generation)
+            .copied()
+            .flatten()
+            .ok_or_else(|| {
+                let family = match self.key_area_key_index {
+                    KeyAreaKeyIndex::Application => "application",
+                    KeyAreaKeyIndex::Ocean => "ocean",
+                    KeyAreaKeyIndex::System => "system",
+                };
+                KeyError::Missing(format!("key_area_key_{}_{:02x}", family, generation))
+            })?;
+        Ok(ecb_decrypt(&key_area_key, &self.key_area[2]))
+    }
+
+    /// Open the filesystem of a section for reading. `nca_offset` is where
+    /// the NCA starts in `reader`.
+    pub fn open_section<R: Read + Seek>(
+        &self,
+        reader: R,
+        nca_offset: u64,
+        section: &NcaSection,
+        keys: &KeySet,
+    ) -> Result<SectionReader<R>, FormatError> {
+        let key = match section.encryption {
+            EncryptionType::None => None,
+            EncryptionType::AesCtr => Some(self.content_key(keys)?),
+            other => {
+                return Err(FormatError::Invalid(format!(
+                    "Unsupported section encryption {:?}",
+                    other
+                )))
+            }
+        };
+
+        let start = section
+            .offset
+            .checked_add(section.fs_offset)
+            .ok_or_else(|| {
+                FormatError::Invalid(format!("Section {} is out of range", section.index))
+            })?;
+
+        Ok(SectionReader {
+            inner: reader,
+            nca_offset,
+            start,
+            len: section.fs_size,
+            pos: 0,
+            key,
+            ctr: section.ctr,
+        })
+    }
+}
+
+/// Decrypted view of the filesystem inside an NCA section
+pub struct SectionReader<R> {
+    inner: R,
+    nca_offset: u64,
+    /// Offset of the filesystem from the start of the NCA
+    start: u64,
+    len: u64,
+    pos: u64,
+    key: Option<Key128>,
+    ctr: u64,
+}
+
+impl<R: Read + Seek> Read for SectionReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        const MAX_CHUNK: u64 = 0x10_0000;
+
+        let remaining = self.len.saturating_sub(self.pos);
+        let wanted = (buf.len() as u64).min(remaining).min(MAX_CHUNK) as usize;
+        if wanted == 0 {
+            return Ok(0);
+        }
+
+        // CTR works on 16 byte blocks, so read from the enclosing block
+        let out_of_range =
+            || io::Error::new(io::ErrorKind::InvalidInput, "Read past the end of the file");
+        let offset = self.start.checked_add(self.pos).ok_or_else(out_of_range)?;
+        let aligned = offset & !0xF;
+        let skip = (offset - aligned) as usize;
+        let mut data = vec![0u8; (skip + wanted).next_multiple_of(16)];
+
+        let file_offset = self
+            .nca_offset
+            .checked_add(aligned)
+            .ok_or_else(out_of_range)?;
+        self.inner.seek(SeekFrom::Start(file_offset))?;
+        self.inner.read_exact(&mut data)?;
+
+        if let Some(key) = &self.key {
+            let mut iv = [0u8; 16];
+            iv[..8].copy_from_slice(&self.ctr.to_be_bytes());
+            iv[8..].copy_from_slice(&(aligned >> 4).to_be_bytes());
+            let mut cipher = Aes128Ctr::new(key.into(), &iv.into());
+            cipher.apply_keystream(&mut data);
+        }
+
+        buf[..wanted].copy_from_slice(&data[skip..skip + wanted]);
+        self.pos += wanted as u64;
+        Ok(wanted)
+    }
+}
+
+impl<R> Seek for SectionReader<R> {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        let new_pos = match pos {
+            SeekFrom::Start(p) => Some(p),
+            SeekFrom::End(d) => self.len.checked_add_signed(d),
+            SeekFrom::Current(d) => self.pos.checked_add_signed(d),
+        };
+        self.pos = new_pos.ok_or_else(|| {
+            io::Error::new(io::ErrorKind::InvalidInput, "Seek before start of section")
+        })?;
+        Ok(self.pos)
+    }
+}
+
+fn ecb_decrypt(key: &Key128, data: &Key128) -> Key128 {
+    let cipher = Aes128::new(key.into());
+    let mut block = GenericArray::from(*data);
+    cipher.decrypt_block(&mut block);
+    block.into()
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// AES-128-XTS decryption with Nintendo's big-endian sector tweak
+fn xts_decrypt(key: &[u8; 32], data: &mut [u8], first_sector: u64) {
+    let data_cipher = Aes128::new(GenericArray::from_slice(&key[..16]));
+    let tweak_cipher = Aes128::new(GenericArray::from_slice(&key[16..]));
+
+    for (i, sector) in data.chunks_mut(SECTOR_SIZE).enumerate() {
+        let sector_number = (first_sector + i as u64) as u128;
+        let mut tweak = GenericArray::from(sector_number.to_be_bytes());
+        tweak_cipher.encrypt_block(&mut tweak);
+
+        for block in sector.chunks_exact_mut(16) {
+            xor_block(block, &tweak);
+            data_cipher.decrypt_block(GenericArray::from_mut_slice(block));
+            xor_block(block, &tweak);
+            gf_mul_x(&mut tweak);
+        }
+    }
+}
+
+fn xor_block(block: &mut [u8], tweak: &[u8]) {
+    for (b, t) in block.iter_mut().zip(tweak) {
+        *b ^= t;
+    }
+}
+
+/// Multiply the XTS tweak by x in GF(2^128), little-endian byte order
+fn gf_mul_x(tweak: &mut [u8]) {
+    let mut carry = 0;
+    for byte in tweak.iter_mut() {
+        let next = *byte >> 7;
+        *byte = (*byte << 1) | carry;
+        carry = next;
+    }
+    if carry != 0 {
+        tweak[0] ^= 0x87;
+    }
+}
+
+/// AES-128-XTS encryption, the inverse of `xts_decrypt`, to build fixtures
+#[cfg(test)]
+pub(crate) fn xts_encrypt(key: &[u8; 32], data: &mut [u8], first_sector: u64) {
+    let data_cipher = Aes128::new(GenericArray::from_slice(&key[..16]));
+    let tweak_cipher = Aes128::new(GenericArray::from_slice(&key[16..]));
+
+    for (i, sector) in data.chunks_mut(SECTOR_SIZE).enumerate() {
+        let sector_number = (first_sector + i as u64) as u128;
+        let mut tweak = GenericArray::from(sector_number.to_be_bytes());
+        tweak_cipher.encrypt_block(&mut tweak);
+
+        for block in sector.chunks_exact_mut(16) {
+            xor_block(block, &tweak);
+            data_cipher.encrypt_block(GenericArray::from_mut_slice(block));
+            xor_block(block, &tweak);
+            gf_mul_x(&mut tweak);
+        }
+    }
+}
+
+#[cfg(test)]
+pub(crate) const TEST_HEADER_KEY: [u8; 32] = [0x42; 32];
+#[cfg(test)]
+const TEST_KEY_AREA_KEY: Key128 = [0x17; 16];
+
+/// Keys matching the NCAs built by the test helpers
+#[cfg(test)]
+pub(crate) fn test_keyset() -> KeySet {
+    let mut keys = KeySet {
+        header_key: Some(TEST_HEADER_KEY),
+        ..Default::default()
+    };
+    keys.key_area_keys[KeyAreaKeyIndex::Application as usize][2] = Some(TEST_KEY_AREA_KEY);
+    keys
+}
+
+/// Build a complete NCA with one AES-CTR encrypted RomFS section for tests
+#[cfg(test)]
+pub(crate) fn build_romfs_nca(content_type: u8, program_id: u64, romfs: &[u8]) -> Vec<u8> {
+    const CONTENT_KEY: Key128 = [0x99; 16];
+    const CTR: u64 = 0x0000_0001_0000_0000;
+
+    let start = NCA_HEADER_SIZE as u64;
+    let end = (start + romfs.len() as u64).next_multiple_of(MEDIA_UNIT);
+
+    let mut header = vec![0u8; NCA_HEADER_SIZE];
+    header[0x200..0x204].copy_from_slice(b"NCA3");
+    header[0x205] = content_type;
+    header[0x220] = 3;
+    header[0x210..0x218].copy_from_slice(&program_id.to_le_bytes());
+    header[0x240..0x244].copy_from_slice(&((start / MEDIA_UNIT) as u32).to_le_bytes());
+    header[0x244..0x248].copy_from_slice(&((end / MEDIA_UNIT) as u32).to_le_bytes());
+
+    let mut encrypted_key = GenericArray::from(CONTENT_KEY);
+    Aes128::new(&TEST_KEY_AREA_KEY.into()).encrypt_block(&mut encrypted_key);
+    header[0x320..0x330].copy_from_slice(&encrypted_key);
+
+    let fs_header = &mut header[FS_HEADER_OFFSET..];
+    fs_header[0] = 2;
+    fs_header[2] = 0;
+    fs_header[4] = 3;
+    fs_header[0x98..0xA0].copy_from_slice(&(romfs.len() as u64).to_le_bytes());
+    fs_header[0x140..0x148].copy_from_slice(&CTR.to_le_bytes());
+    xts_encrypt(&TEST_HEADER_KEY, &mut header, 0);
+
+    let mut body = romfs.to_vec();
+    body.resize((end - start) as usize, 0);
+    let mut iv = [0u8; 16];
+    iv[..8].copy_from_slice(&CTR.to_be_bytes());
+    iv[8..].copy_from_slice(&(start >> 4).to_be_bytes());
+    Aes128Ctr::new(&CONTENT_KEY.into(), &iv.into()).apply_keystream(&mut body);
+
+    header.extend_from_slice(&body);
+    header
+}
+
+/// Build an encrypted NCA3 header for tests. `sections` lists
+/// (start, end) media offsets, filesystem type and encryption type.
+#[cfg(test)]
+pub(crate) fn build_nca_header(
+    header_key: &[u8; 32],
+    content_type: u8,
+    program_id: u64,
+    sections: &[(u32, u32, u8, u8)],
+) -> Vec<u8> {
+    let mut data = vec![0u8; NCA_HEADER_SIZE];
+    data[0x200..0x204].copy_from_slice(b"NCA3");
+    data[0x205] = content_type;
+    data[0x220] = 3;
+    data[0x210..0x218].copy_from_slice(&program_id.to_le_bytes());
+    for (i, (start, end, fs_type, encryption)) in sections.iter().enumerate() {
+        data[0x240 + i * 0x10..][..4].copy_from_slice(&start.to_le_bytes());
+        data[0x244 + i * 0x10..][..4].copy_from_slice(&end.to_le_bytes());
+        let fs_header = &mut data[FS_HEADER_OFFSET + i * FS_HEADER_SIZE..];
+        fs_header[0] = 2;
+        fs_header[2] = *fs_type;
+        fs_header[4] = *encryption;
+    }
+    xts_encrypt(header_key, &mut data, 0);
+    data
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_keys() -> KeySet {
+        KeySet {
+            header_key: Some(TEST_HEADER_KEY),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_xts_matches_ieee_vector() {
+        // IEEE 1619 XTS-AES-128 test vector 1 (all-zero keys, sector 0)
+        let mut data = [0u8; 32];
+        xts_encrypt(&[0u8; 32], &mut data, 0);
+        assert_eq!(
+            data,
+            [
+                0x91, 0x7c, 0xf6, 0x9e, 0xbd, 0x68, 0xb2, 0xec, 0x9b, 0x9f, 0xe9, 0xa3, 0xea, 0xdd,
+                0xa6, 0x92, 0xcd, 0x43, 0xd2, 0xf5, 0x95, 0x98, 0xed, 0x85, 0x8c, 0x02, 0xc2, 0x65,
+                0x2f, 0xbf, 0x92, 0x2e,
+            ]
+        );
+
+        xts_decrypt(&[0u8; 32], &mut data, 0);
+        assert_eq!(data, [0u8; 32]);
+    }
+
+    #[test]
+    fn test_parse_header() {
+        let mut data = build_nca_header(
+            &TEST_HEADER_KEY,
+            2,
+            0x01007EF00011E000,
+            &[(0x6, 0x40, 0, 3)],
+        );
+        let header = NcaHeader::parse(&mut data, &test_keys()).unwrap();
+
+        assert_eq!(header.content_type, ContentType::Control);
+        assert_eq!(header.title_id(), "01007EF00011E000");
+        assert_eq!(header.key_generation, 2);
+        assert_eq!(header.rights_id, [0u8; 16]);
+        assert_eq!(header.sections.len(), 1);
+        assert_eq!(header.sections[0].offset, 0xC00);
+        assert_eq!(header.sections[0].fs_type, FsType::RomFs);
+        assert_eq!(header.sections[0].encryption, EncryptionType::AesCtr);
+    }
+
+    #[test]
+    fn test_read_encrypted_section() {
+        let plain: Vec<u8> = (0..0x345u32).map(|i| i as u8).collect();
+        let nca = build_romfs_nca(2, 0x0100000000010000, &plain);
+        let keys = test_keyset();
+
+        let mut cursor = io::Cursor::new(&nca);
+        let header = NcaHeader::read(&mut cursor, 0, &keys).unwrap();
+        let section = header.sections[0].clone();
+        let mut reader = header.open_section(cursor, 0, &section, &keys).unwrap();
+
+        let mut all = Vec::new();
+        reader.read_to_end(&mut all).unwrap();
+        assert_eq!(all, plain);
+
+        // Unaligned reads decrypt from the middle of a block
+        let mut part = [0u8; 7];
+        reader.seek(SeekFrom::Start(0x123)).unwrap();
+        reader.read_exact(&mut part).unwrap();
+        assert_eq!(&part, &plain[0x123..0x12A]);
+    }
+
+    #[test]
+    fn test_rejects_filesystem_outside_section() {
+        let build = |fs_offset: u64, fs_size: u64| {
+            let mut data =
+                build_nca_header(&TEST_HEADER_KEY, 2, 0x0100000000010000, &[(0x6, 0x8, 0, 3)]);
+            xts_decrypt(&TEST_HEADER_KEY, &mut data, 0);
+            let fs_header = &mut data[FS_HEADER_OFFSET..];
+            fs_header[0x90..0x98].copy_from_slice(&fs_offset.to_le_bytes());
+            fs_header[0x98..0xA0].copy_from_slice(&fs_size.to_le_bytes());
+            xts_encrypt(&TEST_HEADER_KEY, &mut data, 0);
+            data
+        };
+
+        assert!(NcaHeader::parse(&mut build(0x200, 0x200), &test_keys()).is_ok());
+        for (fs_offset, fs_size) in [(0x200, 0x201), (0x401, 0), (u64::MAX, 2)] {
+            let result = NcaHeader::parse(&mut build(fs_offset, fs_size), &test_keys());
+            assert!(matches!(result, Err(FormatError::Invalid(_))));
+        }
+    }
+
+    #[test]
+    fn test_rejects_overflowing_section_offset() {
+        let header = NcaHeader::read(
+            &mut io::Cursor::new(build_romfs_nca(2, 0x0100000000010000, &[0u8; 0x10])),
+            0,
+            &test_keyset(),
+        )
+        .unwrap();
+        let section = NcaSection {
+            offset: u64::MAX,
+            fs_offset: 0x10,
+            encryption: EncryptionType::None,
+            ..header.sections[0].clone()
+        };
+
+        let result = header.open_section(io::empty(), 0, &section, &test_keyset());
+        assert!(matches!(result, Err(FormatError::Invalid(_))));
+    }
+
+    #[test]
+    fn test_read_rejects_overflowing_offsets() {
+        let nca = build_romfs_nca(2, 0x0100000000010000, &[0u8; 0x10]);
+        let keys = test_keyset();
+        let header = NcaHeader::read(&mut io::Cursor::new(&nca), 0, &keys).unwrap();
+        let section = header.sections[0].clone();
+
+        let mut reader = header
+            .open_section(io::Cursor::new(&nca), u64::MAX - 0x10, &section, &keys)
+            .unwrap();
+        let err = reader.read(&mut [0u8; 0x10]).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+
+        // The filesystem start is in range but a position inside it is not
+        let section = NcaSection {
+            offset: u64::MAX - 0x20,
+            fs_offset: 0x10,
+            fs_size: 0x100,
+            ..section
+        };
+        let mut reader = header
+            .open_section(io::Cursor::new(&nca), 0, &section, &keys)
+            .unwrap();
+        reader.seek(SeekFrom::Start(0x20)).unwrap();
+        let err = reader.read(&mut [0u8; 0x10]).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidInput);
+    }
+
+    #[test]
+    fn test_missing_content_key() {
+        let nca = build_romfs_nca(2, 0x0100000000010000, &[0u8; 0x10]);
+        let mut keys = test_keyset();
+        keys.key_area_keys[0][2] = None;
+
+        let header = NcaHeader::read(&mut io::Cursor::new(&nca), 0, &keys).unwrap();
+        let err = header.content_key(&keys).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Key key_area_key_application_02 is missing from prod.keys"
+        );
+    }
+
+    #[test]
+    fn test_wrong_header_key() {
+        let mut data = build_nca_header(&[0x24; 32], 0, 0x0100000000010000, &[]);
+        let result = NcaHeader::parse(&mut data, &test_keys());
+        assert!(matches!(result, Err(FormatError::BadMagic { .. })));
+    }
+
+    #[test]
+    fn test_missing_header_key() {
+        let mut data = vec![0u8; NCA_HEADER_SIZE];
+        let result = NcaHeader::parse(&mut data, &KeySet::default());
+        assert!(matches!(result, Err(FormatError::Keys(_))));
+    }
+}
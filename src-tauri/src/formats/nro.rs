@@ -0,0 +1,177 @@
+//! NRO homebrew executables and their ASET asset section
+
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::Path;
+
+use super::nacp::{Nacp, NACP_SIZE};
+use super::{read_u32, read_u64, FormatError};
+
+const NRO_MAGIC: &[u8; 4] = b"NRO0";
+const ASET_MAGIC: &[u8; 4] = b"ASET";
+const NRO_HEADER_SIZE: u64 = 0x80;
+const ASET_HEADER_SIZE: u64 = 0x38;
+
+// Icons are 256x256 JPEGs; anything larger is not an icon
+const MAX_ICON_SIZE: u64 = 0x10_0000;
+
+/// Assets embedded in an NRO
+#[derive(Debug, Clone, Default)]
+pub struct Nro {
+    pub nacp: Option<Nacp>,
+    /// JPEG icon data
+    pub icon: Option<Vec<u8>>,
+}
+
+impl Nro {
+    /// Open and parse an NRO file
+    pub fn open(path: &Path) -> Result<Self, FormatError> {
+        let mut file = File::open(path)?;
+        let len = file.metadata()?.len();
+        Self::read(&mut file, len)
+    }
+
+    /// Parse the NRO header and, if present, the ASET section after the
+    /// executable. NROs without assets are valid and yield an empty `Nro`.
+    pub fn read<R: Read + Seek>(reader: &mut R, file_size: u64) -> Result<Self, FormatError> {
+        if file_size < NRO_HEADER_SIZE {
+            return Err(FormatError::Truncated {
+                needed: NRO_HEADER_SIZE,
+                available: file_size,
+            });
+        }
+
+        let mut header = [0u8; NRO_HEADER_SIZE as usize];
+        reader.seek(SeekFrom::Start(0))?;
+        reader.read_exact(&mut header)?;
+
+        if &header[0x10..0x14] != NRO_MAGIC {
+            return Err(FormatError::BadMagic {
+                expected: "NRO0",
+                found: header[0x10..0x14].try_into().unwrap(),
+            });
+        }
+
+        let nro_size = read_u32(&header, 0x18) as u64;
+        if nro_size > file_size {
+            return Err(FormatError::Truncated {
+                needed: nro_size,
+                available: file_size,
+            });
+        }
+
+        if file_size < nro_size + ASET_HEADER_SIZE {
+            return Ok(Nro::default());
+        }
+
+        let mut aset = [0u8; ASET_HEADER_SIZE as usize];
+        reader.seek(SeekFrom::Start(nro_size))?;
+        reader.read_exact(&mut aset)?;
+        if &aset[0..4] != ASET_MAGIC {
+            return Ok(Nro::default());
+        }
+
+        let available = file_size - nro_size;
+        let icon = read_section(reader, nro_size, available, &aset, 0x08, MAX_ICON_SIZE)?;
+        let nacp = read_section(reader, nro_size, available, &aset, 0x18, NACP_SIZE as u64)?
+            .map(|data| Nacp::parse(&data))
+            .transpose()?;
+
+        Ok(Nro { nacp, icon })
+    }
+}
+
+/// Read an ASET section described by the (offset, size) pair at
+/// `field` in the ASET header
+fn read_section<R: Read + Seek>(
+    reader: &mut R,
+    aset_start: u64,
+    available: u64,
+    aset: &[u8],
+    field: usize,
+    max_size: u64,
+) -> Result<Option<Vec<u8>>, FormatError> {
+    let offset = read_u64(aset, field);
+    let size = read_u64(aset, field + 8);
+    if size == 0 {
+        return Ok(None);
+    }
+    if size > max_size {
+        return Err(FormatError::Invalid(format!(
+            "ASET section of {} bytes is too large",
+            size
+        )));
+    }
+
+    let end = offset.saturating_add(size);
+    if end > available {
+        return Err(FormatError::Truncated {
+            needed: aset_start + end,
+            available: aset_start + available,
+        });
+    }
+
+    let mut data = vec![0u8; size as usize];
+    reader.seek(SeekFrom::Start(aset_start + offset))?;
+    reader.read_exact(&mut data)?;
+    Ok(Some(data))
+}
+
+/// Build an NRO with an ASET section for tests
+#[cfg(test)]
+pub(crate) fn build_nro(icon: &[u8], nacp: &[u8]) -> Vec<u8> {
+    let nro_size = 0x100u32;
+    let mut image = vec![0u8; nro_size as usize];
+    image[0x10..0x14].copy_from_slice(NRO_MAGIC);
+    image[0x18..0x1C].copy_from_slice(&nro_size.to_le_bytes());
+
+    let icon_offset = ASET_HEADER_SIZE;
+    let nacp_offset = icon_offset + icon.len() as u64;
+    image.extend_from_slice(ASET_MAGIC);
+    image.extend_from_slice(&0u32.to_le_bytes());
+    image.extend_from_slice(&icon_offset.to_le_bytes());
+    image.extend_from_slice(&(icon.len() as u64).to_le_bytes());
+    image.extend_from_slice(&nacp_offset.to_le_bytes());
+    image.extend_from_slice(&(nacp.len() as u64).to_le_bytes());
+    image.extend_from_slice(&[0u8; 16]);
+    image.extend_from_slice(icon);
+    image.extend_from_slice(nacp);
+    image
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::formats::nacp::build_nacp;
+    use std::io::Cursor;
+
+    #[test]
+    fn test_read_assets() {
+        let image = build_nro(b"\xFF\xD8jpeg", &build_nacp("NX-Shell", "joel16", "4.0.1"));
+        let nro = Nro::read(&mut Cursor::new(&image), image.len() as u64).unwrap();
+
+        assert_eq!(nro.icon.as_deref(), Some(&b"\xFF\xD8jpeg"[..]));
+        let nacp = nro.nacp.unwrap();
+        assert_eq!(nacp.titles[0].name, "NX-Shell");
+        assert_eq!(nacp.titles[0].publisher, "joel16");
+        assert_eq!(nacp.display_version, "4.0.1");
+    }
+
+    #[test]
+    fn test_nro_without_assets() {
+        let mut image = build_nro(b"", &[]);
+        image.truncate(0x100);
+        let nro = Nro::read(&mut Cursor::new(&image), image.len() as u64).unwrap();
+        assert!(nro.nacp.is_none());
+        assert!(nro.icon.is_none());
+    }
+
+    #[test]
+    fn test_truncated_aset() {
+        let mut image = build_nro(b"icon", &build_nacp("Name", "Author", "1.0"));
+        image.truncate(image.len() - 0x100);
+        let len = image.len() as u64;
+        let result = Nro::read(&mut Cursor::new(image), len);
+        assert!(matches!(result, Err(FormatError::Truncated { .. })));
+    }
+}
@@ -0,0 +1,243 @@
+//! PFS0 partition filesystem, the container format used by NSP files
+
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::Path;
+
+use super::{read_cstr, read_u32, read_u64, title_id_from_name, FormatError};
+
+const PFS0_MAGIC: &[u8; 4] = b"PFS0";
+const HEADER_SIZE: u64 = 0x10;
+const PFS0_ENTRY_SIZE: u64 = 0x18;
+
+// Real containers have a handful of files; anything beyond this is garbage
+const MAX_ENTRIES: u32 = 0x1000;
+const MAX_STRING_TABLE: u32 = 0x10_0000;
+
+/// A file stored inside a PFS0 or HFS0 container
+#[derive(Debug, Clone, PartialEq)]
+pub struct PartitionEntry {
+    pub name: String,
+    /// Offset of the file data from the start of the reader
+    pub offset: u64,
+    pub size: u64,
+}
+
+/// Parsed PFS0 header and file table
+#[derive(Debug, Clone)]
+pub struct Pfs0 {
+    pub entries: Vec<PartitionEntry>,
+}
+
+impl Pfs0 {
+    /// Open and parse an NSP file
+    pub fn open(path: &Path) -> Result<Self, FormatError> {
+        let mut file = File::open(path)?;
+        let len = file.metadata()?.len();
+        Self::read(&mut file, 0, len)
+    }
+
+    /// Parse a PFS0 that starts at `base` and spans `available` bytes.
+    /// Fails with `Truncated` if the header or any file runs past that span.
+    pub fn read<R: Read + Seek>(
+        reader: &mut R,
+        base: u64,
+        available: u64,
+    ) -> Result<Self, FormatError> {
+        let entries = read_partition(reader, base, available, PFS0_MAGIC, PFS0_ENTRY_SIZE)?;
+        Ok(Pfs0 { entries })
+    }
+
+    /// Title ID derived from the ticket's file name (its rights ID)
+    pub fn title_id(&self) -> Option<String> {
+        title_id_from_entries(&self.entries)
+    }
+}
+
+/// Title ID from the ticket file name in a partition. Other entries are
+/// named after content hashes, so without a ticket the title ID has to
+/// come from the NCA headers.
+pub(crate) fn title_id_from_entries(entries: &[PartitionEntry]) -> Option<String> {
+    entries
+        .iter()
+        .filter(|e| e.name.ends_with(".tik"))
+        .find_map(|e| title_id_from_name(&e.name))
+}
+
+/// Parse the header, file table and string table shared by PFS0 and HFS0.
+/// The two formats only differ in magic and file entry size; the fields
+/// used here sit at the same offsets in both.
+pub(crate) fn read_partition<R: Read + Seek>(
+    reader: &mut R,
+    base: u64,
+    available: u64,
+    magic: &'static [u8; 4],
+    entry_size: u64,
+) -> Result<Vec<PartitionEntry>, FormatError> {
+    if available < HEADER_SIZE {
+        return Err(FormatError::Truncated {
+            needed: HEADER_SIZE,
+            available,
+        });
+    }
+
+    let mut header = [0u8; HEADER_SIZE as usize];
+    reader.seek(SeekFrom::Start(base))?;
+    reader.read_exact(&mut header)?;
+
+    if &header[0..4] != magic {
+        return Err(FormatError::BadMagic {
+            expected: std::str::from_utf8(magic).unwrap_or("?"),
+            found: header[0..4].try_into().unwrap(),
+        });
+    }
+
+    let num_files = read_u32(&header, 4);
+    let string_table_size = read_u32(&header, 8);
+    if num_files > MAX_ENTRIES || string_table_size > MAX_STRING_TABLE {
+        return Err(FormatError::Invalid(format!(
+            "Implausible partition header: {} files, {} byte string table",
+            num_files, string_table_size
+        )));
+    }
+
+    let table_size = entry_size * num_files as u64 + string_table_size as u64;
+    let data_start = HEADER_SIZE + table_size;
+    if data_start > available {
+        return Err(FormatError::Truncated {
+            needed: data_start,
+            available,
+        });
+    }
+
+    let mut table = vec![0u8; table_size as usize];
+    reader.read_exact(&mut table)?;
+    let strings = &table[(entry_size * num_files as u64) as usize..];
+
+    let mut entries = Vec::with_capacity(num_files as usize);
+    for i in 0..num_files as usize {
+        let entry = &table[i * entry_size as usize..];
+        let data_offset = read_u64(entry, 0);
+        let size = read_u64(entry, 8);
+        let name_offset = read_u32(entry, 16) as usize;
+
+        let name = read_cstr(strings, name_offset).ok_or_else(|| {
+            FormatError::Invalid(format!("Name offset {} out of range", name_offset))
+        })?;
+
+        let end = data_start
+            .checked_add(data_offset)
+            .and_then(|v| v.checked_add(size))
+            .ok_or_else(|| FormatError::Invalid(format!("Entry {} overflows", name)))?;
+        if end > available {
+            return Err(FormatError::Truncated {
+                needed: end,
+                available,
+            });
+        }
+
+        entries.push(PartitionEntry {
+            name,
+            offset: base + data_start + data_offset,
+            size,
+        });
+    }
+
+    Ok(entries)
+}
+
+/// Build an in-memory PFS0 image for tests
+#[cfg(test)]
+pub(crate) fn build_pfs0(files: &[(&str, &[u8])]) -> Vec<u8> {
+    build_partition(PFS0_MAGIC, PFS0_ENTRY_SIZE, files)
+}
+
+/// Build an in-memory PFS0/HFS0 image for tests
+#[cfg(test)]
+pub(crate) fn build_partition(
+    magic: &[u8; 4],
+    entry_size: u64,
+    files: &[(&str, &[u8])],
+) -> Vec<u8> {
+    let mut strings = Vec::new();
+    let mut entries = Vec::new();
+    let mut data = Vec::new();
+
+    for (name, contents) in files {
+        entries.extend_from_slice(&(data.len() as u64).to_le_bytes());
+        entries.extend_from_slice(&(contents.len() as u64).to_le_bytes());
+        entries.extend_from_slice(&(strings.len() as u32).to_le_bytes());
+        entries.resize(entries.len() + entry_size as usize - 0x14, 0);
+        strings.extend_from_slice(name.as_bytes());
+        strings.push(0);
+        data.extend_from_slice(contents);
+    }
+    while strings.len() % 0x20 != 0 {
+        strings.push(0);
+    }
+
+    let mut out = Vec::new();
+    out.extend_from_slice(magic);
+    out.extend_from_slice(&(files.len() as u32).to_le_bytes());
+    out.extend_from_slice(&(strings.len() as u32).to_le_bytes());
+    out.extend_from_slice(&0u32.to_le_bytes());
+    out.extend_from_slice(&entries);
+    out.extend_from_slice(&strings);
+    out.extend_from_slice(&data);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    fn sample() -> Vec<u8> {
+        build_pfs0(&[
+            ("0123456789abcdef0123456789abcdef.nca", b"program"),
+            ("fedcba9876543210fedcba9876543210.cnmt.nca", b"meta"),
+            ("01007ef00011e0000000000000000004.tik", b"ticket"),
+            ("01007ef00011e0000000000000000004.cert", b"cert"),
+        ])
+    }
+
+    #[test]
+    fn test_parse_entries() {
+        let image = sample();
+        let mut reader = Cursor::new(&image);
+        let pfs = Pfs0::read(&mut reader, 0, image.len() as u64).unwrap();
+
+        assert_eq!(pfs.entries.len(), 4);
+        assert_eq!(
+            pfs.entries[1].name,
+            "fedcba9876543210fedcba9876543210.cnmt.nca"
+        );
+
+        let ticket = &pfs.entries[2];
+        let mut data = vec![0u8; ticket.size as usize];
+        reader.seek(SeekFrom::Start(ticket.offset)).unwrap();
+        reader.read_exact(&mut data).unwrap();
+        assert_eq!(data, b"ticket");
+        assert_eq!(pfs.title_id().as_deref(), Some("01007EF00011E000"));
+    }
+
+    #[test]
+    fn test_truncated_file() {
+        let mut image = sample();
+        image.truncate(image.len() - 3);
+        let len = image.len() as u64;
+
+        let result = Pfs0::read(&mut Cursor::new(image), 0, len);
+        assert!(matches!(result, Err(FormatError::Truncated { .. })));
+    }
+
+    #[test]
+    fn test_bad_magic() {
+        let mut image = sample();
+        image[0..4].copy_from_slice(b"HFS0");
+        let len = image.len() as u64;
+
+        let result = Pfs0::read(&mut Cursor::new(image), 0, len);
+        assert!(matches!(result, Err(FormatError::BadMagic { .. })));
+    }
+}
@@ -0,0 +1,250 @@
+//! RomFS, the read-only filesystem in NCA data sections
+
+use std::io::{Read, Seek, SeekFrom};
+
+use super::{read_u32, read_u64, FormatError};
+
+const HEADER_SIZE: usize = 0x50;
+const DIR_ENTRY_SIZE: usize = 0x18;
+const FILE_ENTRY_SIZE: usize = 0x20;
+const EMPTY: u32 = 0xFFFF_FFFF;
+
+// Control RomFS tables and files are at most a few hundred KiB; refuse to
+// allocate for garbage
+const MAX_READ_SIZE: u64 = 0x100_0000;
+
+/// A file in the root directory of a RomFS
+#[derive(Debug, Clone, PartialEq)]
+pub struct RomFsFile {
+    pub name: String,
+    /// Offset of the file data from the start of the reader
+    pub offset: u64,
+    pub size: u64,
+}
+
+/// Parsed RomFS root directory
+#[derive(Debug, Clone)]
+pub struct RomFs {
+    pub files: Vec<RomFsFile>,
+}
+
+impl RomFs {
+    /// Parse the RomFS header and list the files in the root directory
+    pub fn read<R: Read + Seek>(reader: &mut R) -> Result<Self, FormatError> {
+        let mut header = [0u8; HEADER_SIZE];
+        reader.seek(SeekFrom::Start(0))?;
+        reader.read_exact(&mut header)?;
+
+        if read_u64(&header, 0) != HEADER_SIZE as u64 {
+            return Err(FormatError::Invalid("Bad RomFS header size".to_string()));
+        }
+
+        let dir_table = read_table(reader, read_u64(&header, 0x18), read_u64(&header, 0x20))?;
+        let file_table = read_table(reader, read_u64(&header, 0x38), read_u64(&header, 0x40))?;
+        let data_offset = read_u64(&header, 0x48);
+
+        if dir_table.len() < DIR_ENTRY_SIZE {
+            return Err(FormatError::Invalid(
+                "RomFS has no root directory".to_string(),
+            ));
+        }
+
+        let mut files = Vec::new();
+        let mut next = read_u32(&dir_table, 0xC);
+        while next != EMPTY {
+            let offset = next as usize;
+            if offset + FILE_ENTRY_SIZE > file_table.len() || files.len() > file_table.len() {
+                return Err(FormatError::Invalid(format!(
+                    "RomFS file entry 0x{:X} out of range",
+                    offset
+                )));
+            }
+
+            let entry = &file_table[offset..];
+            let name_size = read_u32(entry, 0x1C) as usize;
+            let name = entry
+                .get(FILE_ENTRY_SIZE..FILE_ENTRY_SIZE + name_size)
+                .ok_or_else(|| FormatError::Invalid("RomFS file name out of range".to_string()))?;
+
+            let offset = data_offset
+                .checked_add(read_u64(entry, 0x8))
+                .ok_or_else(|| {
+                    FormatError::Invalid("RomFS file offset out of range".to_string())
+                })?;
+            files.push(RomFsFile {
+                name: String::from_utf8_lossy(name).into_owned(),
+                offset,
+                size: read_u64(entry, 0x10),
+            });
+            next = read_u32(entry, 0x4);
+        }
+
+        Ok(RomFs { files })
+    }
+
+    /// Look up a root file by name
+    pub fn find(&self, name: &str) -> Option<&RomFsFile> {
+        self.files.iter().find(|f| f.name == name)
+    }
+
+    /// Read the whole contents of a file
+    pub fn read_file<R: Read + Seek>(
+        &self,
+        reader: &mut R,
+        file: &RomFsFile,
+    ) -> Result<Vec<u8>, FormatError> {
+        read_range(reader, file.offset, file.size, "file")
+    }
+}
+
+fn read_table<R: Read + Seek>(
+    reader: &mut R,
+    offset: u64,
+    size: u64,
+) -> Result<Vec<u8>, FormatError> {
+    read_range(reader, offset, size, "table")
+}
+
+/// Read `size` bytes at `offset`, checking both against the section
+/// before allocating
+fn read_range<R: Read + Seek>(
+    reader: &mut R,
+    offset: u64,
+    size: u64,
+    what: &str,
+) -> Result<Vec<u8>, FormatError> {
+    if size > MAX_READ_SIZE {
+        return Err(FormatError::Invalid(format!(
+            "RomFS {} of {} bytes is too large",
+            what, size
+        )));
+    }
+    let len = reader.seek(SeekFrom::End(0))?;
+    if offset.checked_add(size).filter(|&end| end <= len).is_none() {
+        return Err(FormatError::Invalid(format!(
+            "RomFS {} at 0x{:X} is out of range",
+            what, offset
+        )));
+    }
+
+    let mut data = vec![0u8; size as usize];
+    reader.seek(SeekFrom::Start(offset))?;
+    reader.read_exact(&mut data)?;
+    Ok(data)
+}
+
+/// Build a RomFS with the given files in its root directory for tests
+#[cfg(test)]
+pub(crate) fn build_romfs(files: &[(&str, &[u8])]) -> Vec<u8> {
+    let mut dir_table = Vec::new();
+    dir_table.extend_from_slice(&0u32.to_le_bytes()); // parent
+    dir_table.extend_from_slice(&EMPTY.to_le_bytes()); // sibling
+    dir_table.extend_from_slice(&EMPTY.to_le_bytes()); // child dir
+    let first_file = if files.is_empty() { EMPTY } else { 0 };
+    dir_table.extend_from_slice(&first_file.to_le_bytes());
+    dir_table.extend_from_slice(&EMPTY.to_le_bytes()); // hash chain
+    dir_table.extend_from_slice(&0u32.to_le_bytes()); // name size
+
+    let mut file_table = Vec::new();
+    let mut data = Vec::new();
+    for (i, (name, contents)) in files.iter().enumerate() {
+        let entry_size = (FILE_ENTRY_SIZE + name.len()).next_multiple_of(4);
+        let sibling = if i + 1 < files.len() {
+            (file_table.len() + entry_size) as u32
+        } else {
+            EMPTY
+        };
+
+        file_table.extend_from_slice(&0u32.to_le_bytes());
+        file_table.extend_from_slice(&sibling.to_le_bytes());
+        file_table.extend_from_slice(&(data.len() as u64).to_le_bytes());
+        file_table.extend_from_slice(&(contents.len() as u64).to_le_bytes());
+        file_table.extend_from_slice(&EMPTY.to_le_bytes());
+        file_table.extend_from_slice(&(name.len() as u32).to_le_bytes());
+        file_table.extend_from_slice(name.as_bytes());
+        file_table.resize(file_table.len().next_multiple_of(4), 0);
+
+        data.extend_from_slice(contents);
+        data.resize(data.len().next_multiple_of(0x10), 0);
+    }
+
+    let dir_offset = HEADER_SIZE as u64;
+    let file_offset = dir_offset + dir_table.len() as u64;
+    let data_offset = (file_offset + file_table.len() as u64).next_multiple_of(0x10);
+
+    let mut out = Vec::new();
+    for value in [
+        HEADER_SIZE as u64,
+        dir_offset,
+        0,
+        dir_offset,
+        dir_table.len() as u64,
+        file_offset,
+        0,
+        file_offset,
+        file_table.len() as u64,
+        data_offset,
+    ] {
+        out.extend_from_slice(&value.to_le_bytes());
+    }
+    out.extend_from_slice(&dir_table);
+    out.extend_from_slice(&file_table);
+    out.resize(data_offset as usize, 0);
+    out.extend_from_slice(&data);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    #[test]
+    fn test_list_and_read_root_files() {
+        let image = build_romfs(&[
+            ("control.nacp", b"nacp data"),
+            ("icon_AmericanEnglish.dat", b"jpeg"),
+        ]);
+        let mut reader = Cursor::new(image);
+        let romfs = RomFs::read(&mut reader).unwrap();
+
+        assert_eq!(romfs.files.len(), 2);
+        let icon = romfs.find("icon_AmericanEnglish.dat").unwrap();
+        assert_eq!(romfs.read_file(&mut reader, icon).unwrap(), b"jpeg");
+        let nacp = romfs.find("control.nacp").unwrap();
+        assert_eq!(romfs.read_file(&mut reader, nacp).unwrap(), b"nacp data");
+    }
+
+    #[test]
+    fn test_rejects_garbage() {
+        let result = RomFs::read(&mut Cursor::new(vec![0xAB; 0x100]));
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_rejects_oversized_file() {
+        let mut reader = Cursor::new(build_romfs(&[("control.nacp", b"nacp data")]));
+        let romfs = RomFs::read(&mut reader).unwrap();
+        let nacp = romfs.find("control.nacp").unwrap();
+
+        for size in [MAX_READ_SIZE + 1, 0x1000, u64::MAX] {
+            let file = RomFsFile {
+                size,
+                ..nacp.clone()
+            };
+            let result = romfs.read_file(&mut reader, &file);
+            assert!(matches!(result, Err(FormatError::Invalid(_))));
+        }
+    }
+
+    #[test]
+    fn test_rejects_overflowing_file_offset() {
+        let mut image = build_romfs(&[("control.nacp", b"nacp data")]);
+        // First file entry's data offset, relative to the header's data offset
+        let file_offset = read_u64(&image, 0x38) as usize;
+        image[file_offset + 0x8..][..8].copy_from_slice(&u64::MAX.to_le_bytes());
+
+        let result = RomFs::read(&mut Cursor::new(image));
+        assert!(matches!(result, Err(FormatError::Invalid(_))));
+    }
+}
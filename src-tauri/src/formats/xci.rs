@@ -0,0 +1,247 @@
+//! XCI gamecard images: card header plus HFS0 root and partitions
+
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::Path;
+
+use super::pfs0::{read_partition, title_id_from_entries, PartitionEntry};
+use super::{read_u64, FormatError};
+
+const HEAD_MAGIC: &[u8; 4] = b"HEAD";
+const HFS0_MAGIC: &[u8; 4] = b"HFS0";
+const HFS0_ENTRY_SIZE: u64 = 0x40;
+const CARD_HEADER_SIZE: usize = 0x200;
+const MEDIA_UNIT: u64 = 0x200;
+
+// Some dumping tools prepend the 0x1000 byte key area to the image
+const KEY_AREA_SIZE: u64 = 0x1000;
+
+const GIB: u64 = 1024 * 1024 * 1024;
+
+/// One of the HFS0 partitions in the root (`update`, `normal`, `secure`, `logo`)
+#[derive(Debug, Clone)]
+pub struct XciPartition {
+    pub name: String,
+    pub entries: Vec<PartitionEntry>,
+}
+
+/// Parsed gamecard header and partition tables
+#[derive(Debug, Clone)]
+pub struct Xci {
+    /// Capacity of the cartridge in bytes
+    pub rom_size: u64,
+    /// Bytes actually used by the image; everything after is padding
+    pub data_size: u64,
+    /// Size of the image on disk
+    pub file_size: u64,
+    pub partitions: Vec<XciPartition>,
+}
+
+impl Xci {
+    /// Open and parse an XCI file
+    pub fn open(path: &Path) -> Result<Self, FormatError> {
+        let mut file = File::open(path)?;
+        let len = file.metadata()?.len();
+        Self::read(&mut file, len)
+    }
+
+    /// Parse a gamecard image of `file_size` bytes
+    pub fn read<R: Read + Seek>(reader: &mut R, file_size: u64) -> Result<Self, FormatError> {
+        if file_size < CARD_HEADER_SIZE as u64 {
+            return Err(FormatError::Truncated {
+                needed: CARD_HEADER_SIZE as u64,
+                available: file_size,
+            });
+        }
+
+        let mut header = [0u8; CARD_HEADER_SIZE];
+        let mut base = 0;
+        reader.seek(SeekFrom::Start(0))?;
+        reader.read_exact(&mut header)?;
+
+        if &header[0x100..0x104] != HEAD_MAGIC
+            && file_size >= KEY_AREA_SIZE + CARD_HEADER_SIZE as u64
+        {
+            let mut shifted = [0u8; CARD_HEADER_SIZE];
+            reader.seek(SeekFrom::Start(KEY_AREA_SIZE))?;
+            reader.read_exact(&mut shifted)?;
+            if &shifted[0x100..0x104] == HEAD_MAGIC {
+                header = shifted;
+                base = KEY_AREA_SIZE;
+            }
+        }
+
+        if &header[0x100..0x104] != HEAD_MAGIC {
+            return Err(FormatError::BadMagic {
+                expected: "HEAD",
+                found: header[0x100..0x104].try_into().unwrap(),
+            });
+        }
+
+        let rom_size = rom_size_from_code(header[0x10D]).ok_or_else(|| {
+            FormatError::Invalid(format!("Unknown gamecard size 0x{:02X}", header[0x10D]))
+        })?;
+        let data_size = read_u64(&header, 0x118)
+            .checked_add(1)
+            .and_then(|pages| pages.checked_mul(MEDIA_UNIT))
+            .ok_or_else(|| FormatError::Invalid("Gamecard data size out of range".to_string()))?;
+        let file_size = file_size - base;
+        if data_size > file_size {
+            return Err(FormatError::Truncated {
+                needed: data_size,
+                available: file_size,
+            });
+        }
+
+        let root_offset = base
+            .checked_add(read_u64(&header, 0x130))
+            .ok_or_else(|| FormatError::Invalid("HFS0 root offset out of range".to_string()))?;
+        let root_entries = read_partition(
+            reader,
+            root_offset,
+            data_size.saturating_sub(root_offset - base),
+            HFS0_MAGIC,
+            HFS0_ENTRY_SIZE,
+        )?;
+
+        let mut partitions = Vec::with_capacity(root_entries.len());
+        for root_entry in root_entries {
+            let entries = read_partition(
+                reader,
+                root_entry.offset,
+                root_entry.size,
+                HFS0_MAGIC,
+                HFS0_ENTRY_SIZE,
+            )?;
+            partitions.push(XciPartition {
+                name: root_entry.name,
+                entries,
+            });
+        }
+
+        Ok(Xci {
+            rom_size,
+            data_size,
+            file_size,
+            partitions,
+        })
+    }
+
+    /// Whether the unused padding after the data has been cut off
+    pub fn is_trimmed(&self) -> bool {
+        self.file_size == self.data_size
+    }
+
+    /// Look up a partition by name
+    pub fn partition(&self, name: &str) -> Option<&XciPartition> {
+        self.partitions.iter().find(|p| p.name == name)
+    }
+
+    /// Title ID of the game from a ticket in the secure partition, which
+    /// cartridge dumps usually don't have
+    pub fn title_id(&self) -> Option<String> {
+        title_id_from_entries(&self.partition("secure")?.entries)
+    }
+}
+
+/// Cartridge capacity for the ROM size byte of the card header
+fn rom_size_from_code(code: u8) -> Option<u64> {
+    match code {
+        0xFA => Some(GIB),
+        0xF8 => Some(2 * GIB),
+        0xF0 => Some(4 * GIB),
+        0xE0 => Some(8 * GIB),
+        0xE1 => Some(16 * GIB),
+        0xE2 => Some(32 * GIB),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::formats::pfs0::build_partition;
+    use std::io::Cursor;
+
+    fn hfs0(files: &[(&str, &[u8])]) -> Vec<u8> {
+        build_partition(HFS0_MAGIC, HFS0_ENTRY_SIZE, files)
+    }
+
+    fn sample(padding: usize) -> Vec<u8> {
+        // Cartridges carry no tickets; every entry is named by content hash
+        let secure = hfs0(&[
+            ("3b4f1e2a9c8d7e6f5a4b3c2d1e0f9a8b.nca", b"program"),
+            ("7c2e9a1b4d3f5e6a8b9c0d1e2f3a4b5c.cnmt.nca", b"meta"),
+            ("e1d2c3b4a5968778695a4b3c2d1e0f9a.nca", b"control"),
+        ]);
+        let update = hfs0(&[("aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa.nca", b"firmware")]);
+        let root = hfs0(&[
+            ("update", &update),
+            ("normal", &hfs0(&[])),
+            ("secure", &secure),
+        ]);
+
+        let mut image = vec![0u8; CARD_HEADER_SIZE];
+        image[0x100..0x104].copy_from_slice(HEAD_MAGIC);
+        image[0x10D] = 0xFA;
+        image[0x130..0x138].copy_from_slice(&(CARD_HEADER_SIZE as u64).to_le_bytes());
+        image.extend_from_slice(&root);
+        image.resize(image.len().next_multiple_of(MEDIA_UNIT as usize), 0);
+
+        let data_end_page = (image.len() as u64 / MEDIA_UNIT) - 1;
+        image[0x118..0x120].copy_from_slice(&data_end_page.to_le_bytes());
+        image.resize(image.len() + padding, 0xFF);
+        image
+    }
+
+    #[test]
+    fn test_parse_partitions() {
+        let image = sample(0);
+        let xci = Xci::read(&mut Cursor::new(&image), image.len() as u64).unwrap();
+
+        let names: Vec<_> = xci.partitions.iter().map(|p| p.name.as_str()).collect();
+        assert_eq!(names, vec!["update", "normal", "secure"]);
+        assert_eq!(xci.partition("secure").unwrap().entries.len(), 3);
+        assert_eq!(xci.rom_size, GIB);
+        // Left to the NCA headers rather than guessed from a content hash
+        assert_eq!(xci.title_id(), None);
+        assert!(xci.is_trimmed());
+    }
+
+    #[test]
+    fn test_untrimmed_image() {
+        let image = sample(0x4000);
+        let xci = Xci::read(&mut Cursor::new(&image), image.len() as u64).unwrap();
+        assert!(!xci.is_trimmed());
+    }
+
+    #[test]
+    fn test_rejects_invalid_images() {
+        let mut image = sample(0);
+        image.truncate(image.len() - MEDIA_UNIT as usize);
+        let len = image.len() as u64;
+        let result = Xci::read(&mut Cursor::new(image), len);
+        assert!(matches!(result, Err(FormatError::Truncated { .. })));
+
+        let image = vec![0u8; 0x2000];
+        let result = Xci::read(&mut Cursor::new(image), 0x2000);
+        assert!(matches!(result, Err(FormatError::BadMagic { .. })));
+    }
+
+    #[test]
+    fn test_rejects_out_of_range_header() {
+        let mut header = vec![0xFFu8; CARD_HEADER_SIZE];
+        header[0x100..0x104].copy_from_slice(HEAD_MAGIC);
+        header[0x10D] = 0xFA;
+        let result = Xci::read(&mut Cursor::new(&header), header.len() as u64);
+        assert!(matches!(result, Err(FormatError::Invalid(_))));
+
+        // Valid data size, but a root offset past the end of u64 once the
+        // key area is added
+        let mut image = vec![0u8; KEY_AREA_SIZE as usize];
+        header[0x118..0x120].copy_from_slice(&0u64.to_le_bytes());
+        image.extend_from_slice(&header);
+        let result = Xci::read(&mut Cursor::new(&image), image.len() as u64);
+        assert!(matches!(result, Err(FormatError::Invalid(_))));
+    }
+}
@@ -1,23 +1,368 @@
 use base64::{engine::general_purpose, Engine as _};
+use globset::GlobSet;
+use rayon::prelude::*;
 use serde::{Deserialize, Serialize};
-use std::collections::HashSet;
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::fs;
 use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 use walkdir::WalkDir;
 
-#[derive(Debug, Serialize, Deserialize, Clone)]
+use crate::emulator::{
+    self, Emulator, EmulatorProfile, LaunchOverrides, LaunchTarget, Locations, Playtime,
+    TitleMetadata,
+};
+use crate::formats::nacp::Nacp;
+use crate::formats::nca::{ContentType, FsType, NcaHeader};
+use crate::formats::nro::Nro;
+use crate::formats::pfs0::{PartitionEntry, Pfs0};
+use crate::formats::romfs::RomFs;
+use crate::formats::xci::Xci;
+use crate::formats::FormatError;
+use crate::keys::{self, KeyError, KeySet};
+use crate::process;
+use crate::scan_cache::{Fingerprint, ScanCache, ScanOptions, ScanStats};
+use crate::scan_config::ScanConfig;
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
 pub struct Game {
     pub id: String,
     pub title: String,
     pub path: String,         // Changed to String for serialization
     pub icon: Option<String>, // Base64 encoded icon or URL
     pub emulator: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title_id: Option<String>,
+    /// Publisher, or author for homebrew
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub publisher: Option<String>,
+    /// Display version like "1.2.0"
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub version: Option<String>,
+    /// Official names in every language from the NACP
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub titles: Vec<LocalizedTitle>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub supported_languages: Vec<String>,
+    /// Minimum age by rating organization (ESRB, PEGI, ...)
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub age_ratings: BTreeMap<String, u8>,
+    /// Files found inside the container (NSP/XCI)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub contents: Vec<ContentFile>,
+    /// Gamecard details for XCI images
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cartridge: Option<CartridgeInfo>,
+    /// Set when the file is corrupt or truncated and cannot be played
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    #[serde(default)]
+    pub favorite: bool,
+    /// Playtime recorded by the emulator itself
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub emulator_playtime: Option<Playtime>,
+}
+
+impl Game {
+    /// Pick the title and publisher from `titles` by language preference,
+    /// falling back to English and then to the first available language
+    pub fn localize(&mut self, preferred: &[String]) {
+        let fallback = ["AmericanEnglish", "BritishEnglish"];
+        let title = preferred
+            .iter()
+            .map(String::as_str)
+            .chain(fallback)
+            .find_map(|language| self.titles.iter().find(|t| t.language == language))
+            .or_else(|| self.titles.first());
+
+        if let Some(title) = title.cloned() {
+            self.title = title.name;
+            if !title.publisher.is_empty() {
+                self.publisher = Some(title.publisher);
+            }
+        }
+    }
+
+    /// Take names, version, languages and ratings from a NACP
+    fn apply_nacp(&mut self, nacp: &Nacp) {
+        self.titles = nacp
+            .titles
+            .iter()
+            .map(|t| LocalizedTitle {
+                language: t.language.to_string(),
+                name: t.name.clone(),
+                publisher: t.publisher.clone(),
+            })
+            .collect();
+        if !nacp.display_version.is_empty() {
+            self.version = Some(nacp.display_version.clone());
+        }
+        self.supported_languages = nacp
+            .supported_languages
+            .iter()
+            .map(|l| l.to_string())
+            .collect();
+        self.age_ratings = nacp
+            .age_ratings
+            .iter()
+            .map(|(org, age)| (org.to_string(), *age))
+            .collect();
+        self.localize(&[]);
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct LocalizedTitle {
+    /// NACP language name, e.g. "AmericanEnglish"
+    pub language: String,
+    pub name: String,
+    pub publisher: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum ContentKind {
+    Nca,
+    Meta,
+    Ticket,
+    Cert,
+    Other,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct ContentFile {
+    pub name: String,
+    pub size: u64,
+    pub kind: ContentKind,
+}
+
+impl ContentFile {
+    fn new(name: &str, size: u64) -> Self {
+        let kind = if name.ends_with(".cnmt.nca") || name.ends_with(".cnmt.ncz") {
+            ContentKind::Meta
+        } else if name.ends_with(".nca") || name.ends_with(".ncz") {
+            ContentKind::Nca
+        } else if name.ends_with(".tik") {
+            ContentKind::Ticket
+        } else if name.ends_with(".cert") {
+            ContentKind::Cert
+        } else {
+            ContentKind::Other
+        };
+
+        ContentFile {
+            name: name.to_string(),
+            size,
+            kind,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct CartridgeInfo {
+    /// Cartridge capacity in bytes
+    pub rom_size: u64,
+    /// Bytes of real data in the image
+    pub data_size: u64,
+    pub trimmed: bool,
+    /// Names of the HFS0 partitions (`update`, `normal`, `secure`, ...)
+    pub partitions: Vec<String>,
+}
+
+/// Decrypted header of one NCA inside a container
+#[derive(Debug, Serialize)]
+pub struct NcaInfo {
+    pub name: String,
+    pub header: Option<NcaHeader>,
+    pub error: Option<String>,
+}
+
+/// Directory inside the app data directory where extracted icons are kept
+pub const ICON_CACHE_DIR: &str = "icons";
+
+/// File extensions of games we list
+const GAME_EXTENSIONS: [&str; 3] = ["nsp", "xci", "nro"];
+
+/// Error returned by a scan stopped through its `ScanMonitor`
+pub const SCAN_CANCELLED: &str = "Scan cancelled";
+
+/// Shortest time between two progress reports within one folder
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(100);
+
+/// A folder a scan looked in and the emulator its games belong to
+#[derive(Debug, Clone, PartialEq)]
+pub struct ScanRoot {
+    pub path: PathBuf,
+    pub emulator: String,
+    /// WalkDir depth; 1 searches only the folder itself
+    pub max_depth: usize,
+}
+
+impl ScanRoot {
+    /// Whether `path` is within this root's depth
+    fn covers(&self, path: &Path) -> bool {
+        path.strip_prefix(&self.path)
+            .is_ok_and(|rest| rest.components().count() <= self.max_depth)
+    }
+}
+
+/// Game IDs added, removed and rebuilt by an incremental update
+#[derive(Debug, Serialize, Clone, Default, PartialEq)]
+pub struct LibraryChange {
+    pub added: Vec<String>,
+    pub removed: Vec<String>,
+    pub updated: Vec<String>,
+}
+
+/// How far a running scan has got, emitted as `scan-progress`
+#[derive(Debug, Serialize, Clone, Default, PartialEq)]
+pub struct ScanProgress {
+    /// Folder being walked
+    pub root: String,
+    pub files_seen: usize,
+    pub games_found: usize,
+}
+
+/// Lets another thread follow a scan and stop it. Shared by the scan's
+/// worker threads.
+pub struct ScanMonitor {
+    cancelled: Arc<AtomicBool>,
+    report: Box<dyn Fn(&ScanProgress) + Send + Sync>,
+    progress: Mutex<(ScanProgress, Option<Instant>)>,
+}
+
+impl ScanMonitor {
+    /// Call `report` as the scan moves on. Setting `cancelled` stops the
+    /// scan at the next file.
+    pub fn new(
+        cancelled: Arc<AtomicBool>,
+        report: impl Fn(&ScanProgress) + Send + Sync + 'static,
+    ) -> Self {
+        ScanMonitor {
+            cancelled,
+            report: Box::new(report),
+            progress: Mutex::new((ScanProgress::default(), None)),
+        }
+    }
+
+    fn check(&self) -> Result<(), String> {
+        if self.cancelled.load(Ordering::Relaxed) {
+            Err(SCAN_CANCELLED.to_string())
+        } else {
+            Ok(())
+        }
+    }
+
+    fn enter(&self, root: &Path) {
+        self.update(true, |progress| {
+            progress.root = root.to_string_lossy().to_string();
+        });
+    }
+
+    fn file_seen(&self, is_game: bool) {
+        self.update(false, |progress| {
+            progress.files_seen += 1;
+            if is_game {
+                progress.games_found += 1;
+            }
+        });
+    }
+
+    /// Apply `change` and report it if `now` or if the last report was a
+    /// while ago
+    fn update(&self, now: bool, change: impl FnOnce(&mut ScanProgress)) {
+        let Ok(mut guard) = self.progress.lock() else {
+            return;
+        };
+        let (progress, last_report) = &mut *guard;
+        change(progress);
+        if now || last_report.is_some_and(|last| last.elapsed() >= PROGRESS_INTERVAL) {
+            (self.report)(progress);
+            *last_report = Some(Instant::now());
+        }
+    }
+
+    #[cfg(test)]
+    fn progress(&self) -> ScanProgress {
+        self.progress.lock().unwrap().0.clone()
+    }
+}
+
+impl std::fmt::Debug for ScanMonitor {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ScanMonitor")
+            .field("cancelled", &self.cancelled)
+            .field("progress", &self.progress)
+            .finish_non_exhaustive()
+    }
+}
+
+/// A game read by a scan worker, before the scanner records it
+enum FoundGame {
+    /// Taken from the scan cache
+    Cached(Game),
+    /// Read from the file, with the fingerprint to cache it under
+    Parsed(Game, Option<Fingerprint>),
+}
+
+/// The games found by a full scan and how they were obtained
+#[derive(Debug, Serialize)]
+pub struct ScanResult {
+    pub games: Vec<Game>,
+    pub stats: ScanStats,
+}
+
+impl LibraryChange {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.updated.is_empty()
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct GameScanner {
     pub games: Vec<Game>,
     seen_paths: HashSet<String>,
+    /// Console keys for reading encrypted NCAs, if the user has them
+    #[serde(skip)]
+    keys: Option<KeySet>,
+    /// Where icons extracted from control NCAs are cached, by title ID
+    #[serde(skip)]
+    icon_cache: Option<PathBuf>,
+    /// Fall back to tinfoil.media and placeholder URLs for missing icons
+    #[serde(skip)]
+    remote_icons: bool,
+    /// Folders scanned since the last reset, in scan order
+    #[serde(skip)]
+    roots: Vec<ScanRoot>,
+    /// Games from earlier scans, reused for unchanged files
+    #[serde(skip)]
+    cache: ScanCache,
+    #[serde(skip)]
+    stats: ScanStats,
+    #[serde(skip)]
+    monitor: Option<ScanMonitor>,
+    /// Where and how to look, from the settings
+    #[serde(skip)]
+    config: ScanConfig,
+    /// `config.excludes`, compiled
+    #[serde(skip)]
+    excludes: GlobSet,
+    /// Where emulators keep their data, found once per scan
+    #[serde(skip)]
+    locations: Option<Locations>,
+    /// Changes made while a full scan runs, to redo on its result
+    #[serde(skip)]
+    pending: Option<PendingChanges>,
+}
+
+/// Watcher updates and manual additions that arrive during a full scan
+#[derive(Debug, Default)]
+struct PendingChanges {
+    paths: Vec<PathBuf>,
+    added: Vec<Game>,
 }
 
 impl GameScanner {
@@ -25,57 +370,369 @@ impl GameScanner {
         GameScanner {
             games: Vec::new(),
             seen_paths: HashSet::new(),
+            keys: None,
+            icon_cache: None,
+            remote_icons: false,
+            roots: Vec::new(),
+            cache: ScanCache::default(),
+            stats: ScanStats::default(),
+            monitor: None,
+            config: ScanConfig::default(),
+            excludes: GlobSet::empty(),
+            locations: None,
+            pending: None,
+        }
+    }
+
+    /// Look for keys, emulator data and game folders in `locations`
+    pub fn set_locations(&mut self, locations: Locations) {
+        self.locations = Some(locations);
+    }
+
+    pub fn locations(&self) -> Option<&Locations> {
+        self.locations.as_ref()
+    }
+
+    /// Scan the user's folders and skip excluded paths as `config` says
+    pub fn set_scan_config(&mut self, config: ScanConfig) -> Result<(), String> {
+        self.excludes = config.exclude_set()?;
+        self.config = config;
+        Ok(())
+    }
+
+    /// Reuse and persist parsed games with `cache`
+    pub fn set_scan_cache(&mut self, cache: ScanCache) {
+        self.cache = cache;
+    }
+
+    /// Cache extracted icons in `dir` so they survive rescans without keys
+    pub fn set_icon_cache(&mut self, dir: PathBuf) {
+        self.icon_cache = Some(dir);
+    }
+
+    /// Allow hotlinking remote icons for games without one of their own
+    pub fn set_remote_icons(&mut self, enabled: bool) {
+        self.remote_icons = enabled;
+    }
+
+    /// Load prod.keys/title.keys from the emulator key directories.
+    /// Without keys, metadata only comes from file names and headers.
+    pub fn load_keys(&mut self) -> Result<(), KeyError> {
+        let loaded = match &self.locations {
+            Some(locations) => keys::load_keys_in(locations),
+            None => Err(KeyError::NotFound {
+                searched: Vec::new(),
+            }),
+        };
+        match loaded {
+            Ok(keys) => {
+                self.keys = Some(keys);
+                Ok(())
+            }
+            Err(e) => {
+                self.keys = None;
+                Err(e)
+            }
+        }
+    }
+
+    /// An empty scanner with the same settings for a full scan, reporting
+    /// to `monitor`. It takes the scan cache along; the scan replaces this
+    /// scanner's games with `finish_scan`, or gives the cache back with
+    /// `abandon_scan`. Changes made here in the meantime are kept.
+    pub fn start_scan(&mut self, monitor: ScanMonitor) -> GameScanner {
+        self.pending = Some(PendingChanges::default());
+        GameScanner {
+            icon_cache: self.icon_cache.clone(),
+            remote_icons: self.remote_icons,
+            cache: std::mem::take(&mut self.cache),
+            monitor: Some(monitor),
+            config: self.config.clone(),
+            excludes: self.excludes.clone(),
+            ..GameScanner::new()
+        }
+    }
+
+    /// Keep what a failed or cancelled scan parsed, without its games
+    pub fn abandon_scan(&mut self, scan: GameScanner) {
+        self.cache = scan.cache;
+        self.pending = None;
+    }
+
+    /// Take the games of a completed `scan` and redo the changes made here
+    /// while it ran. Cached files the scan didn't find again are forgotten.
+    /// Returns how the scan obtained its games.
+    pub fn finish_scan(&mut self, mut scan: GameScanner) -> ScanStats {
+        scan.monitor = None;
+        let seen = &scan.seen_paths;
+        scan.stats.removed = scan.cache.retain(|path| seen.contains(path));
+        if let Err(e) = scan.cache.save() {
+            log::warn!("Failed to save scan cache: {}", e);
+        }
+
+        let pending = self.pending.take().unwrap_or_default();
+        *self = scan;
+        for game in pending.added {
+            if !self.games.iter().any(|g| g.path == game.path) {
+                self.games.push(game);
+            }
+        }
+        if !pending.paths.is_empty() {
+            self.update_paths(&pending.paths);
+        }
+        self.stats
+    }
+
+    /// Folders scanned since the last reset, for watching
+    pub fn roots(&self) -> &[ScanRoot] {
+        &self.roots
+    }
+
+    /// Scan the user's folders and the game folders of `emulators`, and
+    /// attach what each emulator knows about its titles to the games found
+    /// for it. Files found in more than one place go to the user's folder
+    /// or else to the first emulator that lists them.
+    pub fn scan_emulators(&mut self, emulators: &[&dyn Emulator]) -> Result<(), String> {
+        match self.locations.clone() {
+            Some(locations) => self.scan_emulators_in(emulators, &locations),
+            None => Ok(()),
+        }
+    }
+
+    fn scan_emulators_in(
+        &mut self,
+        emulators: &[&dyn Emulator],
+        locations: &Locations,
+    ) -> Result<(), String> {
+        let user_roots: Vec<ScanRoot> = self
+            .config
+            .folders
+            .iter()
+            .map(|folder| ScanRoot {
+                path: folder.path.clone(),
+                emulator: folder.emulator.clone(),
+                max_depth: folder.max_depth,
+            })
+            .collect();
+
+        let builtin_locations = self.config.builtin_locations;
+        let emulator_roots: Vec<Vec<ScanRoot>> = emulators
+            .par_iter()
+            .map(|emulator| {
+                let mut game_dirs = emulator.game_dirs(locations);
+                if builtin_locations {
+                    game_dirs.extend(emulator.default_game_dirs(locations));
+                }
+
+                // Remove duplicates, keeping the deepest scan of each directory
+                game_dirs.sort_by(|a, b| a.path.cmp(&b.path).then(b.max_depth.cmp(&a.max_depth)));
+                game_dirs.dedup_by(|a, b| a.path == b.path);
+
+                game_dirs
+                    .into_iter()
+                    .map(|game_dir| ScanRoot {
+                        path: game_dir.path,
+                        emulator: emulator.id().to_string(),
+                        max_depth: game_dir.max_depth,
+                    })
+                    .collect()
+            })
+            .collect();
+        self.scan_roots(&[user_roots, emulator_roots.concat()].concat())?;
+
+        let titles: Vec<_> = emulators
+            .par_iter()
+            .map(|emulator| emulator.title_metadata(locations))
+            .collect();
+        for (emulator, titles) in emulators.iter().zip(titles) {
+            self.apply_title_metadata(emulator.id(), &titles);
+        }
+        Ok(())
+    }
+
+    /// Find and read the games in `roots`. Roots are walked and files are
+    /// read in parallel, but the result is the same as going through them
+    /// one by one: games come in root order, then file name order, and a
+    /// file under more than one root belongs to the first.
+    pub fn scan_roots(&mut self, roots: &[ScanRoot]) -> Result<(), String> {
+        let found = roots
+            .par_iter()
+            .map(|root| self.walk_root(root))
+            .collect::<Result<Vec<_>, String>>()?;
+        self.roots.extend_from_slice(roots);
+
+        // Files not seen before in this scan, in order
+        let mut files = Vec::new();
+        for (root, paths) in roots.iter().zip(found) {
+            for path in paths {
+                if !self.seen_paths.insert(path.to_string_lossy().to_string()) {
+                    continue;
+                }
+                let title = path
+                    .file_stem()
+                    .map(|stem| Self::clean_title(&stem.to_string_lossy()))
+                    .unwrap_or_default();
+                if !title.is_empty() {
+                    files.push((path, title, root.emulator.as_str()));
+                }
+            }
+        }
+
+        let options = self.scan_options();
+        let monitor = self.monitor.as_ref();
+        let found = files
+            .into_par_iter()
+            .map(|(path, title, emulator)| {
+                if let Some(monitor) = monitor {
+                    monitor.check()?;
+                }
+                Ok(self.find_game(&path, title, emulator, options))
+            })
+            .collect::<Result<Vec<_>, String>>()?;
+        for found in found {
+            let (game, _) = self.store_game(found, options);
+            self.games.push(game);
         }
+        Ok(())
     }
 
-    /// Reset the scanner state before a new scan
-    pub fn reset(&mut self) {
-        self.games.clear();
-        self.seen_paths.clear();
-    }
-
-    /// Scan for Yuzu games
-    pub fn scan_yuzu(&mut self) -> Result<(), String> {
-        if let Some(home_dir) = dirs::home_dir() {
-            // Typical Yuzu game directory locations
-            let mut yuzu_paths = vec![
-                home_dir.join(".local/share/yuzu/load"),
-                home_dir.join("AppData/Roaming/yuzu/load"),
-                home_dir.join("Library/Application Support/yuzu/load"),
-                // Additional common game storage locations
-                home_dir.join("Documents/Yuzu/games"),
-                home_dir.join("Games/Switch"),
-                home_dir.join("Games/Yuzu"),
-                home_dir.join("Downloads"),
-                home_dir.join("Downloads/Switch"),
-            ];
-
-            // Add custom game directories from Yuzu config (including portable installs)
-            let config_paths = self.find_yuzu_config_paths(&home_dir);
-            for config_path in config_paths {
-                let config_dirs = self.read_yuzu_game_dirs_from_config(&config_path);
-                yuzu_paths.extend(config_dirs);
-
-                // For portable installs, derive the user directory from config path
-                if let Some(config_dir) = config_path.parent() {
-                    if let Some(user_dir) = config_dir.parent() {
-                        yuzu_paths.push(user_dir.join("load"));
-                        yuzu_paths.push(user_dir.join("nand/user/Contents/registered"));
+    /// Bring the games under `paths`, which changed on disk, up to date
+    /// without rescanning everything. Files are attributed to the first
+    /// scan root that covers them, like a full scan does.
+    pub fn update_paths(&mut self, paths: &[PathBuf]) -> LibraryChange {
+        if let Some(pending) = &mut self.pending {
+            pending.paths.extend_from_slice(paths);
+        }
+        let mut change = LibraryChange::default();
+        let mut emulators = HashSet::new();
+
+        for path in paths {
+            // Deleted or moved away: drop the file or everything under it
+            let gone: Vec<String> = self
+                .games
+                .iter()
+                .filter(|g| Path::new(&g.path).starts_with(path) && !Path::new(&g.path).exists())
+                .map(|g| g.path.clone())
+                .collect();
+            for game_path in gone {
+                if let Some(id) = self.remove_game(&game_path) {
+                    change.removed.push(id);
+                }
+            }
+
+            let Some(root) = self.roots.iter().find(|r| r.covers(path)).cloned() else {
+                continue;
+            };
+            let excluded = path
+                .ancestors()
+                .take_while(|p| p.starts_with(&root.path))
+                .any(|p| self.excludes.is_match(p));
+            let files: Vec<PathBuf> = if excluded {
+                Vec::new()
+            } else if path.is_dir() {
+                let depth = path
+                    .strip_prefix(&root.path)
+                    .map_or(0, |p| p.components().count());
+                WalkDir::new(path)
+                    .max_depth(root.max_depth - depth)
+                    .follow_links(self.config.follow_symlinks)
+                    .into_iter()
+                    .filter_entry(|e| !self.excludes.is_match(e.path()))
+                    .filter_map(|e| e.ok())
+                    .filter(|e| e.file_type().is_file())
+                    .map(|e| e.into_path())
+                    .collect()
+            } else if path.is_file() {
+                vec![path.clone()]
+            } else {
+                Vec::new()
+            };
+
+            for file in files.iter().filter(|f| is_game_file(f)) {
+                let Some(title) = file
+                    .file_stem()
+                    .map(|stem| Self::clean_title(&stem.to_string_lossy()))
+                    .filter(|title| !title.is_empty())
+                else {
+                    continue;
+                };
+
+                let path_str = file.to_string_lossy().to_string();
+                match self.games.iter().position(|g| g.path == path_str) {
+                    Some(i) => {
+                        // Keep the emulator it was first found for
+                        let emulator = self.games[i].emulator.clone();
+                        let (game, reused) = self.load_game(file, title, &emulator);
+                        if reused {
+                            // Touched but not changed
+                            continue;
+                        }
+                        change.updated.push(game.id.clone());
+                        self.games[i] = game;
+                        emulators.insert(emulator);
+                    }
+                    None => {
+                        let (game, _) = self.load_game(file, title, &root.emulator);
+                        change.added.push(game.id.clone());
+                        self.seen_paths.insert(path_str);
+                        self.games.push(game);
+                        emulators.insert(root.emulator.clone());
                     }
                 }
             }
+        }
+
+        if let Some(locations) = self.locations.clone() {
+            for emulator in emulators.iter().filter_map(|id| emulator::get(id)) {
+                self.apply_title_metadata(emulator.id(), &emulator.title_metadata(&locations));
+            }
+        }
+
+        // A file may have been re-added after being removed in one batch
+        change.removed.retain(|id| !change.added.contains(id));
+        if !change.is_empty() {
+            if let Err(e) = self.cache.save() {
+                log::warn!("Failed to save scan cache: {}", e);
+            }
+        }
+        change
+    }
+
+    /// Forget the game at `path`, returning its ID
+    fn remove_game(&mut self, path: &str) -> Option<String> {
+        self.seen_paths.remove(path);
+        self.cache.remove(path);
+        let i = self.games.iter().position(|g| g.path == path)?;
+        Some(self.games.remove(i).id)
+    }
 
-            // Remove duplicates
-            yuzu_paths.sort();
-            yuzu_paths.dedup();
+    /// Fill in names, icons, favorites and playtime from an emulator's
+    /// per-title data for the games found for that emulator
+    fn apply_title_metadata(&mut self, emulator: &str, titles: &HashMap<String, TitleMetadata>) {
+        for game in self.games.iter_mut().filter(|g| g.emulator == emulator) {
+            let Some(title) = game
+                .title_id
+                .as_ref()
+                .and_then(|id| titles.get(&id.to_uppercase()))
+            else {
+                continue;
+            };
 
-            for yuzu_path in yuzu_paths {
-                if yuzu_path.exists() {
-                    self.scan_directory(&yuzu_path, "yuzu")?;
+            // NACP names and embedded icons are more reliable than the emulator's
+            if game.titles.is_empty() {
+                if let Some(name) = &title.name {
+                    game.title = name.clone();
                 }
             }
+            let has_own_icon = game.icon.as_ref().is_some_and(|i| i.starts_with("data:"));
+            if !has_own_icon && title.icon.is_some() {
+                game.icon = title.icon.clone();
+            }
+            if let Some(favorite) = title.favorite {
+                game.favorite = favorite;
+            }
+            game.emulator_playtime = title.playtime;
         }
-        Ok(())
     }
 
     /// Clean up noisy filename tags like [0100...][v0]
@@ -103,206 +760,358 @@ impl GameScanner {
         out.split_whitespace().collect::<Vec<_>>().join(" ")
     }
 
-    /// Find possible Yuzu config paths (standard + portable installs)
-    fn find_yuzu_config_paths(&self, home_dir: &Path) -> Vec<PathBuf> {
-        let mut config_paths = vec![
-            home_dir.join("AppData/Roaming/yuzu/qt-config.ini"),
-            home_dir.join(".local/share/yuzu/qt-config.ini"),
-            home_dir.join("Library/Application Support/yuzu/qt-config.ini"),
-        ];
+    /// Game files under `root` that aren't excluded, in file name order
+    fn walk_root(&self, root: &ScanRoot) -> Result<Vec<PathBuf>, String> {
+        let monitor = self.monitor.as_ref();
+        if let Some(monitor) = monitor {
+            monitor.check()?;
+            monitor.enter(&root.path);
+        }
 
-        // Try to discover portable installs in Downloads (limited depth)
-        let downloads_dir = home_dir.join("Downloads");
-        if downloads_dir.exists() {
-            for entry in WalkDir::new(downloads_dir)
-                .max_depth(6)
-                .into_iter()
-                .filter_map(|e| e.ok())
-            {
-                if entry.file_type().is_file() {
-                    if let Some(name) = entry.file_name().to_str() {
-                        if name.eq_ignore_ascii_case("qt-config.ini") {
-                            let path = entry.path();
-                            if path.to_string_lossy().to_ascii_lowercase().contains("yuzu") {
-                                config_paths.push(path.to_path_buf());
-                            }
-                        }
-                    }
-                }
+        let mut files = Vec::new();
+        for entry in WalkDir::new(&root.path)
+            .max_depth(root.max_depth)
+            .follow_links(self.config.follow_symlinks)
+            .sort_by_file_name()
+            .into_iter()
+            .filter_entry(|e| !self.excludes.is_match(e.path()))
+            .filter_map(|e| e.ok())
+        {
+            let is_game = entry.file_type().is_file() && is_game_file(entry.path());
+            if let Some(monitor) = monitor {
+                monitor.check()?;
+                monitor.file_seen(is_game);
+            }
+            if is_game {
+                files.push(entry.into_path());
             }
         }
-
-        config_paths
+        Ok(files)
     }
 
-    /// Parse Yuzu qt-config.ini for custom game directories
-    fn read_yuzu_game_dirs_from_config(&self, config_path: &Path) -> Vec<PathBuf> {
-        let mut dirs = Vec::new();
-        let Ok(contents) = fs::read_to_string(config_path) else {
-            return dirs;
-        };
+    fn scan_options(&self) -> ScanOptions {
+        ScanOptions {
+            keys: self.keys.is_some(),
+            remote_icons: self.remote_icons,
+        }
+    }
 
-        for line in contents.lines() {
-            let line = line.trim();
-            if line.is_empty() || line.starts_with('#') || line.starts_with('[') {
-                continue;
-            }
+    /// The game entry for a file, taken from the scan cache if the file is
+    /// unchanged since it was parsed. Returns whether it was reused.
+    fn load_game(&mut self, path: &Path, title: String, emulator: &str) -> (Game, bool) {
+        let options = self.scan_options();
+        let found = self.find_game(path, title, emulator, options);
+        self.store_game(found, options)
+    }
 
-            let Some((key, value)) = line.split_once('=') else {
-                continue;
-            };
+    /// Look a file up in the scan cache, or read it if it changed
+    fn find_game(
+        &self,
+        path: &Path,
+        title: String,
+        emulator: &str,
+        options: ScanOptions,
+    ) -> FoundGame {
+        let fingerprint = fs::metadata(path).ok().map(|m| Fingerprint::of(&m));
+
+        let path_str = path.to_string_lossy();
+        let cached = fingerprint
+            .as_ref()
+            .and_then(|fp| self.cache.get(&path_str, fp, options));
+        match cached {
+            Some(game) => FoundGame::Cached(Game {
+                emulator: emulator.to_string(),
+                ..game.clone()
+            }),
+            None => FoundGame::Parsed(self.build_game(path, title, emulator), fingerprint),
+        }
+    }
 
-            let key_l = key.trim().to_ascii_lowercase();
-            let mut value = value.trim().trim_matches('"').to_string();
-            if value.is_empty() {
-                continue;
+    /// Count a found game and cache it if it was parsed. Returns whether
+    /// it came from the cache.
+    fn store_game(&mut self, found: FoundGame, options: ScanOptions) -> (Game, bool) {
+        match found {
+            FoundGame::Cached(game) => {
+                self.stats.reused += 1;
+                (game, true)
             }
-
-            // Accept entries that look like Yuzu game directory keys
-            let looks_like_game_dir_key =
-                (key_l.contains("gamedir") || key_l.contains("gamedirs") || key_l.contains("game_directory"))
-                    && (key_l.contains("path") || key_l.ends_with("gamedir") || key_l.ends_with("gamedirs"));
-
-            if looks_like_game_dir_key {
-                // Normalize common path formats
-                if value.starts_with("@ByteArray(") {
-                    // Ignore bytearray entries
-                    continue;
+            FoundGame::Parsed(game, fingerprint) => {
+                self.stats.parsed += 1;
+                if let Some(fingerprint) = fingerprint {
+                    self.cache.insert(fingerprint, options, game.clone());
                 }
-                // QSettings may escape backslashes, keep as-is; PathBuf handles it
-                dirs.push(PathBuf::from(value));
+                (game, false)
             }
         }
-
-        dirs
     }
 
-    /// Scan for Ryujinx games
-    pub fn scan_ryujinx(&mut self) -> Result<(), String> {
-        if let Some(home_dir) = dirs::home_dir() {
-            // Typical Ryujinx configuration directory locations
-            let ryujinx_config_dirs = vec![
-                home_dir.join(".config/Ryujinx"),
-                home_dir.join("AppData/Roaming/Ryujinx"),
-                home_dir.join("Library/Application Support/Ryujinx"),
-            ];
+    /// Create a game entry for a file, reading whatever metadata the
+    /// container format exposes
+    fn build_game(&self, path: &Path, title: String, emulator: &str) -> Game {
+        let path_str = path.to_string_lossy().to_string();
+        let mut game = Game {
+            id: format!("{:x}", md5::compute(path_str.as_bytes())),
+            title,
+            path: path_str,
+            icon: None,
+            emulator: emulator.to_string(),
+            ..Default::default()
+        };
 
-            // First, try to find and parse Ryujinx's application database
-            for config_dir in &ryujinx_config_dirs {
-                if config_dir.exists() {
-                    let _ = self.scan_ryujinx_database(&config_dir);
+        let ext = path
+            .extension()
+            .map(|e| e.to_string_lossy().to_ascii_lowercase())
+            .unwrap_or_default();
+        match ext.as_str() {
+            "nsp" => self.read_nsp_metadata(path, &mut game),
+            "xci" => self.read_xci_metadata(path, &mut game),
+            "nro" => Self::read_nro_metadata(path, &mut game),
+            _ => {}
+        }
 
-                    // Also scan common game directories
-                    let games_dir = config_dir.join("games");
-                    if games_dir.exists() {
-                        self.scan_directory(&games_dir, "ryujinx")?;
-                    }
+        // Try multiple icon strategies, preferring one embedded in the file.
+        // Remote URLs break offline, so they are only used when enabled.
+        game.icon = game
+            .icon
+            .take()
+            .or_else(|| self.find_game_icon(path))
+            .or_else(|| self.cached_icon(game.title_id.as_deref()?))
+            .or_else(|| {
+                if !self.remote_icons {
+                    return None;
                 }
-            }
+                game.title_id
+                    .as_deref()
+                    .map(title_id_icon_url)
+                    .or_else(|| self.extract_title_id_and_fetch_icon(path))
+                    .or_else(|| self.get_default_icon())
+            });
+
+        game
+    }
 
-            // Also check for portable Ryujinx installations
-            let portable_paths = vec![
-                PathBuf::from("C:/Ryujinx/games"),
-                home_dir.join("Ryujinx/games"),
-            ];
+    /// Fill in title ID and contents from the NSP's PFS0 header
+    fn read_nsp_metadata(&self, path: &Path, game: &mut Game) {
+        match Pfs0::open(path) {
+            Ok(pfs) => {
+                game.title_id = pfs.title_id();
+                self.read_nca_metadata(path, &pfs.entries, game);
+                game.contents = pfs
+                    .entries
+                    .iter()
+                    .map(|e| ContentFile::new(&e.name, e.size))
+                    .collect();
+            }
+            Err(e) => {
+                log::warn!("Failed to read NSP {}: {}", path.display(), e);
+                game.error = Some(e.to_string());
+            }
+        }
+    }
 
-            for portable_path in portable_paths {
-                if portable_path.exists() {
-                    self.scan_directory(&portable_path, "ryujinx")?;
+    /// Fill in cartridge details, title ID and contents from the XCI headers
+    fn read_xci_metadata(&self, path: &Path, game: &mut Game) {
+        match Xci::open(path) {
+            Ok(xci) => {
+                game.title_id = xci.title_id();
+                if let Some(secure) = xci.partition("secure") {
+                    self.read_nca_metadata(path, &secure.entries, game);
+                    game.contents = secure
+                        .entries
+                        .iter()
+                        .map(|e| ContentFile::new(&e.name, e.size))
+                        .collect();
                 }
+                game.cartridge = Some(CartridgeInfo {
+                    rom_size: xci.rom_size,
+                    data_size: xci.data_size,
+                    trimmed: xci.is_trimmed(),
+                    partitions: xci.partitions.iter().map(|p| p.name.clone()).collect(),
+                });
+            }
+            Err(e) => {
+                log::warn!("Failed to read XCI {}: {}", path.display(), e);
+                game.error = Some(format!("Not a valid gamecard dump: {}", e));
             }
         }
-        Ok(())
     }
 
-    /// Scan Ryujinx's application database for games with icons
-    fn scan_ryujinx_database(&mut self, config_dir: &Path) -> Result<(), String> {
-        // Ryujinx stores application info in a SQLite database or JSON files
-        // The games.json or title.json file may contain game information
-        let games_json = config_dir.join("games.json");
-        let cache_dir = config_dir.join("bis/user/save/0000000000000000/0000000000000000/cache");
+    /// Read the NCA headers of a container for its title ID (for dumps
+    /// without a ticket) and the Control NCA's NACP. Needs keys.
+    fn read_nca_metadata(&self, path: &Path, entries: &[PartitionEntry], game: &mut Game) {
+        let Some(keys) = self.keys.as_ref() else {
+            return;
+        };
+        let Ok(mut file) = fs::File::open(path) else {
+            return;
+        };
 
-        // Try to find icon cache in Ryujinx's cache directory
-        if cache_dir.exists() {
-            for entry in WalkDir::new(&cache_dir)
-                .max_depth(2)
-                .into_iter()
-                .filter_map(|e| e.ok())
-            {
-                if let Some(file_name) = entry.file_name().to_str() {
-                    if file_name.ends_with(".jpg") || file_name.ends_with(".png") {
-                        // This might be a cached game icon
-                        // The file name should contain the title ID
-                        if let Some(parent) = entry.path().parent() {
-                            if let Some(title_id) = parent.file_name() {
-                                let title_id_str = title_id.to_string_lossy().to_string();
-                                // Only process if it looks like a title ID (16 hex chars)
-                                if title_id_str.len() == 16
-                                    && title_id_str.chars().all(|c| c.is_ascii_hexdigit())
-                                {
-                                    if let Ok(icon_data) = fs::read(entry.path()) {
-                                        let icon_base64 =
-                                            general_purpose::STANDARD.encode(&icon_data);
-                                        // Try to find the corresponding game file
-                                        // For now, we'll just note that we found an icon for this title ID
-                                        log::info!("Found icon for title ID: {}", title_id_str);
-                                    }
+        for entry in entries.iter().filter(|e| e.name.ends_with(".nca")) {
+            let header = match NcaHeader::read(&mut file, entry.offset, keys) {
+                Ok(header) => header,
+                Err(e) => {
+                    log::warn!(
+                        "Failed to read NCA header {} in {}: {}",
+                        entry.name,
+                        path.display(),
+                        e
+                    );
+                    continue;
+                }
+            };
+
+            match header.content_type {
+                ContentType::Program if game.title_id.is_none() => {
+                    game.title_id = Some(header.title_id());
+                }
+                ContentType::Control => {
+                    if game.title_id.is_none() {
+                        game.title_id = Some(header.title_id());
+                    }
+                    match Self::read_control(&mut file, entry, &header, keys) {
+                        Ok((nacp, icon)) => {
+                            game.apply_nacp(&nacp);
+                            if let Some(icon) = icon {
+                                if let Some(title_id) = &game.title_id {
+                                    self.cache_icon(title_id, &icon);
                                 }
+                                game.icon = Some(jpeg_data_url(&icon));
                             }
                         }
+                        Err(e) => {
+                            log::warn!("Failed to read control.nacp in {}: {}", path.display(), e)
+                        }
                     }
                 }
+                _ => {}
             }
         }
+    }
 
-        Ok(())
+    /// Mount the RomFS of a Control NCA, parse its control.nacp and read
+    /// the `icon_<Language>.dat` JPEG of the first available language
+    fn read_control(
+        file: &mut fs::File,
+        entry: &PartitionEntry,
+        header: &NcaHeader,
+        keys: &KeySet,
+    ) -> Result<(Nacp, Option<Vec<u8>>), FormatError> {
+        let section = header
+            .sections
+            .iter()
+            .find(|s| s.fs_type == FsType::RomFs)
+            .ok_or_else(|| FormatError::Invalid("Control NCA has no RomFS".to_string()))?;
+
+        let mut reader = header.open_section(file, entry.offset, section, keys)?;
+        let romfs = RomFs::read(&mut reader)?;
+        let nacp_file = romfs
+            .find("control.nacp")
+            .ok_or_else(|| FormatError::Invalid("RomFS has no control.nacp".to_string()))?;
+        let nacp = Nacp::parse(&romfs.read_file(&mut reader, nacp_file)?)?;
+
+        let icon_file = nacp
+            .titles
+            .iter()
+            .find_map(|t| romfs.find(&format!("icon_{}.dat", t.language)))
+            .or_else(|| {
+                romfs
+                    .files
+                    .iter()
+                    .find(|f| f.name.starts_with("icon_") && f.name.ends_with(".dat"))
+            });
+        let icon = icon_file
+            .map(|f| romfs.read_file(&mut reader, f))
+            .transpose()?;
+
+        Ok((nacp, icon))
     }
 
-    /// Scan a directory for Switch game files
-    fn scan_directory(&mut self, path: &Path, emulator: &str) -> Result<(), String> {
-        // Look for .nsp, .xci, .nca, .nro files
-        let game_extensions = vec!["nsp", "xci", "nro"];
+    fn icon_cache_path(&self, title_id: &str) -> Option<PathBuf> {
+        let dir = self.icon_cache.as_ref()?;
+        Some(dir.join(format!("{}.jpg", title_id.to_uppercase())))
+    }
 
-        for entry in WalkDir::new(path)
-            .max_depth(3)
-            .into_iter()
-            .filter_map(|e| e.ok())
-        {
-            let path = entry.path();
-            if let Some(ext) = path.extension() {
-                if game_extensions.contains(&ext.to_str().unwrap_or("")) {
-                    if let Some(file_name) = path.file_stem() {
-                        let path_str = path.to_string_lossy().to_string();
-                        if self.seen_paths.contains(&path_str) {
-                            continue;
-                        }
-                        self.seen_paths.insert(path_str.clone());
+    /// Icon previously extracted for a title, as a data URL
+    fn cached_icon(&self, title_id: &str) -> Option<String> {
+        let data = fs::read(self.icon_cache_path(title_id)?).ok()?;
+        Some(jpeg_data_url(&data))
+    }
 
-                        let raw_title = file_name.to_string_lossy().to_string();
-                        let title = Self::clean_title(&raw_title);
-                        if title.is_empty() {
-                            continue;
-                        }
-                        let id = format!("{:x}", md5::compute(path_str.as_bytes()));
-
-                        // Try multiple icon strategies
-                        let icon = self
-                            .find_game_icon(path)
-                            .or_else(|| self.extract_title_id_and_fetch_icon(path))
-                            .or_else(|| self.get_default_icon());
-
-                        self.games.push(Game {
-                            id,
-                            title,
-                            path: path_str,
-                            icon,
-                            emulator: emulator.to_string(),
-                        });
-                    }
-                }
+    fn cache_icon(&self, title_id: &str, jpeg: &[u8]) {
+        let Some(path) = self.icon_cache_path(title_id) else {
+            return;
+        };
+        let result = path
+            .parent()
+            .map_or(Ok(()), fs::create_dir_all)
+            .and_then(|_| {
+                // Scan workers may cache the same title at once, and others
+                // may be reading it; only ever show a complete file
+                static NEXT_TMP: AtomicUsize = AtomicUsize::new(0);
+                let n = NEXT_TMP.fetch_add(1, Ordering::Relaxed);
+                let tmp = path.with_extension(format!("{}.tmp", n));
+                fs::write(&tmp, jpeg).and_then(|_| fs::rename(&tmp, &path))
+            });
+        if let Err(e) = result {
+            log::warn!("Failed to cache icon {}: {}", path.display(), e);
+        }
+    }
+
+    /// Decrypt the header of every NCA in an NSP or in an XCI's secure
+    /// partition
+    pub fn inspect_ncas(&self, path: &Path) -> Result<Vec<NcaInfo>, String> {
+        let keys = self
+            .keys
+            .as_ref()
+            .ok_or_else(|| "No keys loaded".to_string())?;
+
+        let ext = path
+            .extension()
+            .map(|e| e.to_string_lossy().to_ascii_lowercase())
+            .unwrap_or_default();
+        let entries = match ext.as_str() {
+            "nsp" => Pfs0::open(path)?.entries,
+            "xci" => Xci::open(path)?
+                .partition("secure")
+                .map(|p| p.entries.clone())
+                .unwrap_or_default(),
+            _ => return Err("Only NSP and XCI files contain NCAs".to_string()),
+        };
+
+        let mut file = fs::File::open(path).map_err(|e| e.to_string())?;
+        let mut infos = Vec::new();
+        for entry in entries.iter().filter(|e| e.name.ends_with(".nca")) {
+            let (header, error) = match NcaHeader::read(&mut file, entry.offset, keys) {
+                Ok(header) => (Some(header), None),
+                Err(e) => (None, Some(e.to_string())),
+            };
+            infos.push(NcaInfo {
+                name: entry.name.clone(),
+                header,
+                error,
+            });
+        }
+        Ok(infos)
+    }
+
+    /// Fill in name, author, version and icon from the NRO's ASET section
+    fn read_nro_metadata(path: &Path, game: &mut Game) {
+        let nro = match Nro::open(path) {
+            Ok(nro) => nro,
+            Err(e) => {
+                log::warn!("Failed to read NRO {}: {}", path.display(), e);
+                game.error = Some(e.to_string());
+                return;
             }
+        };
+
+        if let Some(nacp) = &nro.nacp {
+            game.apply_nacp(nacp);
+        }
+
+        if let Some(icon) = &nro.icon {
+            game.icon = Some(jpeg_data_url(icon));
         }
-        Ok(())
     }
 
     /// Try to extract title ID from filename and fetch icon from online source
@@ -315,11 +1124,7 @@ impl GameScanner {
             for word in name_str.split(|c: char| !c.is_ascii_alphanumeric()) {
                 if word.len() == 16 && word.chars().all(|c| c.is_ascii_hexdigit()) {
                     // Found a potential title ID
-                    // Return URL to tinfoil.media icon
-                    return Some(format!(
-                        "https://tinfoil.media/ti/{}/512/512",
-                        word.to_uppercase()
-                    ));
+                    return Some(title_id_icon_url(word));
                 }
             }
         }
@@ -406,123 +1211,64 @@ impl GameScanner {
         if !path.exists() {
             return Err("Game file does not exist".to_string());
         }
+        // Use the registry's spelling of built-in emulator IDs
+        let emulator = emulator::get(&emulator).map_or(emulator, |e| e.id().to_string());
 
-        let id = format!("{:x}", md5::compute(path.to_string_lossy().as_bytes()));
-        let icon = self
-            .find_game_icon(&path)
-            .or_else(|| self.extract_title_id_and_fetch_icon(&path))
-            .or_else(|| self.get_default_icon());
-
-        let game = Game {
-            id,
-            title,
-            path: path.to_string_lossy().to_string(),
-            icon,
-            emulator,
-        };
+        let game = self.build_game(&path, title, &emulator);
 
+        if let Some(pending) = &mut self.pending {
+            pending.added.push(game.clone());
+        }
         self.games.push(game.clone());
         Ok(game)
     }
 }
 
-/// Launch a game with the specified emulator
-pub fn launch_game_process(game: &Game) -> Result<std::process::Child, String> {
-    use std::process::Command;
-    use std::path::PathBuf;
-
-    fn candidate_paths(file_name: &str) -> Vec<PathBuf> {
-        let mut paths = Vec::new();
-
-        if let Ok(env_path) = std::env::var("YUZU_PATH") {
-            let env_path = PathBuf::from(env_path);
-            if env_path.is_dir() {
-                paths.push(env_path.join(file_name));
-            } else {
-                paths.push(env_path);
-            }
-        }
-
-        if let Some(local_app_data) = dirs::data_local_dir() {
-            paths.push(local_app_data.join("yuzu").join(file_name));
-            paths.push(local_app_data.join("Programs").join("yuzu").join(file_name));
-        }
-
-        paths.push(PathBuf::from("C:/Program Files/yuzu").join(file_name));
-        paths.push(PathBuf::from("C:/Program Files (x86)/yuzu").join(file_name));
-
-        // Portable installs in Downloads (limited depth)
-        if let Some(home_dir) = dirs::home_dir() {
-            let downloads_dir = home_dir.join("Downloads");
-            if downloads_dir.exists() {
-                for entry in WalkDir::new(downloads_dir)
-                    .max_depth(5)
-                    .into_iter()
-                    .filter_map(|e| e.ok())
-                {
-                    if entry.file_type().is_file() {
-                        if let Some(name) = entry.file_name().to_str() {
-                            if name.eq_ignore_ascii_case(file_name)
-                                && entry
-                                    .path()
-                                    .to_string_lossy()
-                                    .to_ascii_lowercase()
-                                    .contains("yuzu")
-                            {
-                                paths.push(entry.path().to_path_buf());
-                            }
-                        }
-                    }
-                }
-            }
-        }
-
-        paths
-    }
-
-    fn resolve_emulator_cmd(emulator: &str) -> Result<PathBuf, String> {
-        if cfg!(target_os = "windows") {
-            let file_name = match emulator {
-                "yuzu" => "yuzu.exe",
-                "ryujinx" => "Ryujinx.exe",
-                _ => return Err("Unknown emulator".to_string()),
-            };
-
-            for path in candidate_paths(file_name) {
-                if path.exists() {
-                    return Ok(path);
-                }
-            }
-
-            // Fallback to PATH lookup
-            return Ok(PathBuf::from(file_name));
-        }
+fn jpeg_data_url(jpeg: &[u8]) -> String {
+    format!(
+        "data:image/jpeg;base64,{}",
+        general_purpose::STANDARD.encode(jpeg)
+    )
+}
 
-        Ok(PathBuf::from(match emulator {
-            "yuzu" => "yuzu",
-            "ryujinx" => "Ryujinx",
-            _ => return Err("Unknown emulator".to_string()),
-        }))
-    }
+/// URL of the tinfoil.media icon for a title ID
+fn title_id_icon_url(title_id: &str) -> String {
+    format!(
+        "https://tinfoil.media/ti/{}/512/512",
+        title_id.to_uppercase()
+    )
+}
 
-    let emulator_cmd = resolve_emulator_cmd(game.emulator.as_str())?;
+fn is_game_file(path: &Path) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| GAME_EXTENSIONS.contains(&ext))
+}
 
-    let mut cmd = Command::new(&emulator_cmd);
-    if let Some(parent) = emulator_cmd.parent() {
-        cmd.current_dir(parent);
+/// Launch a game with its emulator's profile, or the profile its
+/// overrides pick, with the overrides applied. The emulator's stdout and
+/// stderr are piped for the caller to collect, see `SessionLog::capture`.
+pub fn launch_game_process(
+    game: &Game,
+    profiles: &[EmulatorProfile],
+    overrides: &LaunchOverrides,
+    locations: Option<&Locations>,
+) -> Result<std::process::Child, String> {
+    if let Some(error) = &game.error {
+        return Err(format!("Game file is damaged: {}", error));
     }
-    if game.emulator == "yuzu" {
-        cmd.arg("-f");
-        cmd.arg("-g");
-    }
-    cmd.arg(&game.path);
-
-    let result = cmd.spawn();
 
-    match result {
-        Ok(child) => Ok(child),
-        Err(e) => Err(format!("Failed to launch game: {}", e)),
-    }
+    let emulator = overrides.emulator.as_deref().unwrap_or(&game.emulator);
+    let profile = emulator::profile(emulator, profiles, locations)
+        .ok_or_else(|| format!("Unknown emulator: {}", emulator))?
+        .with_overrides(overrides);
+    let mut cmd = profile.command(&LaunchTarget {
+        path: Path::new(&game.path),
+        title_id: game.title_id.as_deref(),
+    })?;
+    cmd.stdout(Stdio::piped()).stderr(Stdio::piped());
+
+    process::spawn(cmd).map_err(|e| format!("Failed to launch game: {}", e))
 }
 
 #[cfg(test)]
@@ -573,9 +1319,568 @@ mod tests {
             path: "/test/game.nsp".to_string(),
             icon: Some("https://example.com/icon.png".to_string()),
             emulator: "ryujinx".to_string(),
+            ..Default::default()
         };
 
         assert_eq!(game.title, "Test Game");
         assert_eq!(game.emulator, "ryujinx");
     }
+
+    #[test]
+    fn test_nsp_metadata() {
+        let dir = tempfile::tempdir().unwrap();
+        let scanner = GameScanner::new();
+
+        let good = dir.path().join("Good.nsp");
+        fs::write(
+            &good,
+            crate::formats::pfs0::build_pfs0(&[
+                ("0123456789abcdef0123456789abcdef.nca", b"program"),
+                ("01007ef00011e0000000000000000004.tik", b"ticket"),
+            ]),
+        )
+        .unwrap();
+        let game = scanner.build_game(&good, "Good".to_string(), "yuzu");
+        assert_eq!(game.title_id.as_deref(), Some("01007EF00011E000"));
+        assert_eq!(game.contents.len(), 2);
+        assert_eq!(game.contents[1].kind, ContentKind::Ticket);
+        assert!(game.error.is_none());
+
+        let bad = dir.path().join("Bad.nsp");
+        fs::write(&bad, b"PFS0\x02").unwrap();
+        let game = scanner.build_game(&bad, "Bad".to_string(), "yuzu");
+        assert!(game.error.is_some());
+        assert!(launch_game_process(&game, &[], &LaunchOverrides::default(), None).is_err());
+    }
+
+    #[test]
+    fn test_nro_metadata() {
+        let dir = tempfile::tempdir().unwrap();
+        let scanner = GameScanner::new();
+
+        let path = dir.path().join("nxshell.nro");
+        fs::write(
+            &path,
+            crate::formats::nro::build_nro(
+                b"\xFF\xD8",
+                &crate::formats::nacp::build_nacp("NX-Shell", "joel16", "4.0.1"),
+            ),
+        )
+        .unwrap();
+
+        let game = scanner.build_game(&path, "nxshell".to_string(), "yuzu");
+        assert_eq!(game.title, "NX-Shell");
+        assert_eq!(game.publisher.as_deref(), Some("joel16"));
+        assert_eq!(game.version.as_deref(), Some("4.0.1"));
+        assert_eq!(game.icon.as_deref(), Some("data:image/jpeg;base64,/9g="));
+    }
+
+    #[test]
+    fn test_title_id_from_nca_header() {
+        use crate::formats::nca::{build_nca_header, TEST_HEADER_KEY};
+
+        let dir = tempfile::tempdir().unwrap();
+        let mut scanner = GameScanner::new();
+        scanner.keys = Some(KeySet {
+            header_key: Some(TEST_HEADER_KEY),
+            ..Default::default()
+        });
+
+        let path = dir.path().join("NoTicket.nsp");
+        let nca = build_nca_header(&TEST_HEADER_KEY, 0, 0x0100ABCD00001000, &[]);
+        fs::write(
+            &path,
+            crate::formats::pfs0::build_pfs0(&[("0123456789abcdef0123456789abcdef.nca", &nca)]),
+        )
+        .unwrap();
+
+        let game = scanner.build_game(&path, "NoTicket".to_string(), "yuzu");
+        assert_eq!(game.title_id.as_deref(), Some("0100ABCD00001000"));
+    }
+
+    #[test]
+    fn test_control_nacp_metadata() {
+        use crate::formats::nacp::build_nacp;
+        use crate::formats::nca::{build_romfs_nca, test_keyset};
+        use crate::formats::romfs::build_romfs;
+
+        let dir = tempfile::tempdir().unwrap();
+        let mut scanner = GameScanner::new();
+        scanner.keys = Some(test_keyset());
+
+        let mut nacp = build_nacp("Super Game", "Nintendo", "1.2.0");
+        // Japanese title at entry 2, English and Japanese supported
+        let japanese = "スーパーゲーム".as_bytes();
+        nacp[2 * 0x300..2 * 0x300 + japanese.len()].copy_from_slice(japanese);
+        nacp[0x302C] = 0b101;
+        nacp[0x3040 + 6] = 12;
+
+        let romfs = build_romfs(&[("control.nacp", &nacp)]);
+        let nca = build_romfs_nca(2, 0x0100ABCD00000800, &romfs);
+        let path = dir.path().join("game.nsp");
+        fs::write(
+            &path,
+            crate::formats::pfs0::build_pfs0(&[("0123456789abcdef0123456789abcdef.nca", &nca)]),
+        )
+        .unwrap();
+
+        let mut game = scanner.build_game(&path, "game".to_string(), "yuzu");
+        assert!(game.error.is_none());
+        assert_eq!(game.title, "Super Game");
+        assert_eq!(game.publisher.as_deref(), Some("Nintendo"));
+        assert_eq!(game.version.as_deref(), Some("1.2.0"));
+        assert_eq!(game.title_id.as_deref(), Some("0100ABCD00000800"));
+        assert_eq!(
+            game.supported_languages,
+            vec!["AmericanEnglish", "Japanese"]
+        );
+        assert_eq!(game.age_ratings.get("PEGI"), Some(&12));
+
+        game.localize(&["Japanese".to_string()]);
+        assert_eq!(game.title, "スーパーゲーム");
+    }
+
+    #[test]
+    fn test_control_icon_is_cached() {
+        use crate::formats::nacp::build_nacp;
+        use crate::formats::nca::{build_romfs_nca, test_keyset};
+        use crate::formats::pfs0::build_pfs0;
+        use crate::formats::romfs::build_romfs;
+
+        let dir = tempfile::tempdir().unwrap();
+        let cache = dir.path().join("icons");
+        let mut scanner = GameScanner::new();
+        scanner.keys = Some(test_keyset());
+        scanner.set_icon_cache(cache.clone());
+
+        let nacp = build_nacp("Super Game", "Nintendo", "1.0.0");
+        let romfs = build_romfs(&[
+            ("control.nacp", &nacp),
+            ("icon_Japanese.dat", b"\xFF\xD8jp"),
+            ("icon_AmericanEnglish.dat", b"\xFF\xD8en"),
+        ]);
+        let nca = build_romfs_nca(2, 0x0100ABCD00000800, &romfs);
+        let path = dir.path().join("game.nsp");
+        fs::write(
+            &path,
+            build_pfs0(&[
+                ("0123456789abcdef0123456789abcdef.nca", &nca),
+                ("0100abcd000008000000000000000000.tik", b""),
+            ]),
+        )
+        .unwrap();
+
+        let game = scanner.build_game(&path, "game".to_string(), "yuzu");
+        let english_icon = jpeg_data_url(b"\xFF\xD8en");
+        assert_eq!(game.icon.as_deref(), Some(english_icon.as_str()));
+        assert!(cache.join("0100ABCD00000800.jpg").is_file());
+
+        // Without keys the NACP can't be read, but the cached icon is used
+        let mut scanner = GameScanner::new();
+        scanner.set_icon_cache(cache);
+        let game = scanner.build_game(&path, "game".to_string(), "yuzu");
+        assert_eq!(game.icon.as_deref(), Some(english_icon.as_str()));
+    }
+
+    #[test]
+    fn test_remote_icons_are_opt_in() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("Game [0100000000010000].nsp");
+        fs::write(&path, b"").unwrap();
+
+        let mut scanner = GameScanner::new();
+        let game = scanner.build_game(&path, "Game".to_string(), "yuzu");
+        assert!(game.icon.is_none());
+
+        scanner.set_remote_icons(true);
+        let game = scanner.build_game(&path, "Game".to_string(), "yuzu");
+        assert!(game.icon.unwrap().contains("tinfoil.media"));
+    }
+
+    #[test]
+    fn test_scan_emulator_attaches_title_metadata() {
+        use crate::formats::pfs0::build_pfs0;
+
+        let home = tempfile::tempdir().unwrap();
+        let config_dir = home.path().join(".config/Ryujinx");
+        let game_dir = home.path().join("Switch Games");
+        fs::create_dir_all(&game_dir).unwrap();
+        fs::write(
+            game_dir.join("game.nsp"),
+            build_pfs0(&[("0100abcd000008000000000000000000.tik", b"")]),
+        )
+        .unwrap();
+
+        let title_dir = config_dir.join("games/0100abcd00000800");
+        fs::create_dir_all(title_dir.join("gui")).unwrap();
+        fs::write(
+            title_dir.join("gui/metadata.json"),
+            r#"{"title": "Ryujinx Name", "favorite": true, "timespan_played": "01:00:00"}"#,
+        )
+        .unwrap();
+        fs::write(
+            config_dir.join("Config.json"),
+            serde_json::json!({ "game_dirs": [game_dir] }).to_string(),
+        )
+        .unwrap();
+
+        let mut scanner = GameScanner::new();
+        let ryujinx = emulator::get("ryujinx").unwrap();
+        let locations = Locations::find(home.path(), true);
+        scanner.scan_emulators_in(&[ryujinx], &locations).unwrap();
+
+        let games = scanner.get_games();
+        assert_eq!(games.len(), 1);
+        assert_eq!(games[0].emulator, "ryujinx");
+        assert_eq!(games[0].title, "Ryujinx Name");
+        assert!(games[0].favorite);
+        assert_eq!(games[0].emulator_playtime.unwrap().seconds, 3600);
+    }
+
+    #[test]
+    fn test_scan_config() {
+        use crate::scan_config::ScanFolder;
+
+        let home = tempfile::tempdir().unwrap();
+        let switch = home.path().join("Games/Switch");
+        fs::create_dir_all(switch.join("Updates")).unwrap();
+        fs::create_dir_all(home.path().join("Downloads")).unwrap();
+        for name in [
+            "Games/Switch/a.nsp",
+            "Games/Switch/Updates/u.nsp",
+            "Downloads/d.nsp",
+        ] {
+            fs::write(home.path().join(name), b"").unwrap();
+        }
+
+        let mut config = ScanConfig {
+            folders: vec![ScanFolder {
+                path: switch.clone(),
+                emulator: "ryujinx".to_string(),
+                max_depth: 2,
+            }],
+            excludes: vec!["**/Updates".to_string()],
+            ..Default::default()
+        };
+        let yuzu = emulator::get("yuzu").unwrap();
+        let locations = Locations::find(home.path(), true);
+        let found = |config: &ScanConfig| {
+            let mut scanner = GameScanner::new();
+            scanner.set_scan_config(config.clone()).unwrap();
+            scanner.scan_emulators_in(&[yuzu], &locations).unwrap();
+            scanner
+                .get_games()
+                .into_iter()
+                .map(|g| (g.title, g.emulator))
+                .collect::<Vec<_>>()
+        };
+
+        // The user's folder wins over yuzu's built-in Games/Switch
+        assert_eq!(
+            found(&config),
+            vec![
+                ("a".to_string(), "ryujinx".to_string()),
+                ("d".to_string(), "yuzu".to_string()),
+            ]
+        );
+
+        config.builtin_locations = false;
+        assert_eq!(
+            found(&config),
+            vec![("a".to_string(), "ryujinx".to_string())]
+        );
+    }
+
+    #[test]
+    fn test_ryujinx_portable_game_dir() {
+        let home = tempfile::tempdir().unwrap();
+        let games_dir = home.path().join("Ryujinx/games");
+        fs::create_dir_all(&games_dir).unwrap();
+        fs::write(games_dir.join("Game.nsp"), b"").unwrap();
+        let ryujinx = emulator::get("ryujinx").unwrap();
+        let locations = Locations::find(home.path(), true);
+
+        let mut scanner = GameScanner::new();
+        scanner.scan_emulators_in(&[ryujinx], &locations).unwrap();
+        let games = scanner.get_games();
+        assert_eq!(games.len(), 1);
+        assert_eq!(games[0].emulator, "ryujinx");
+
+        let mut scanner = GameScanner::new();
+        let config = ScanConfig {
+            builtin_locations: false,
+            ..Default::default()
+        };
+        scanner.set_scan_config(config).unwrap();
+        scanner.scan_emulators_in(&[ryujinx], &locations).unwrap();
+        assert!(scanner.get_games().is_empty());
+    }
+
+    fn scan_root(path: &Path, emulator: &str, max_depth: usize) -> ScanRoot {
+        ScanRoot {
+            path: path.to_path_buf(),
+            emulator: emulator.to_string(),
+            max_depth,
+        }
+    }
+
+    #[test]
+    fn test_scan_roots_order() {
+        let dir = tempfile::tempdir().unwrap();
+        let games = dir.path().join("Games");
+        fs::create_dir_all(games.join("Switch")).unwrap();
+        for name in ["b.nsp", "a.xci", "c.nro", "Switch/d.nsp", "Switch/0.nsp"] {
+            fs::write(games.join(name), b"").unwrap();
+        }
+
+        // The same files through overlapping roots of two emulators
+        let roots = [
+            scan_root(&games.join("Switch"), "yuzu", 1),
+            scan_root(&games, "ryujinx", 2),
+        ];
+        for _ in 0..3 {
+            let mut scanner = GameScanner::new();
+            scanner.scan_roots(&roots).unwrap();
+            let found: Vec<_> = scanner
+                .games
+                .iter()
+                .map(|g| (g.title.as_str(), g.emulator.as_str()))
+                .collect();
+            assert_eq!(
+                found,
+                vec![
+                    ("0", "yuzu"),
+                    ("d", "yuzu"),
+                    ("a", "ryujinx"),
+                    ("b", "ryujinx"),
+                    ("c", "ryujinx"),
+                ]
+            );
+        }
+    }
+
+    #[test]
+    fn test_update_paths() {
+        let home = tempfile::tempdir().unwrap();
+        let root = home.path().join("Games");
+        fs::create_dir_all(root.join("nested/deeper")).unwrap();
+        fs::write(root.join("First.nsp"), b"").unwrap();
+
+        let mut scanner = GameScanner::new();
+        scanner
+            .scan_roots(&[scan_root(&root, "ryujinx", 2)])
+            .unwrap();
+        let first_id = scanner.games[0].id.clone();
+
+        // New file, and a folder moved in with one game too deep to list
+        fs::write(root.join("Second.xci"), b"").unwrap();
+        fs::write(root.join("nested/Third.nro"), b"").unwrap();
+        fs::write(root.join("nested/deeper/Hidden.nsp"), b"").unwrap();
+        fs::write(root.join("notes.txt"), b"").unwrap();
+        let change = scanner.update_paths(&[
+            root.join("Second.xci"),
+            root.join("nested"),
+            root.join("notes.txt"),
+        ]);
+        assert_eq!(change.added.len(), 2);
+        assert!(change.removed.is_empty());
+        let titles: Vec<_> = scanner.games.iter().map(|g| g.title.as_str()).collect();
+        assert_eq!(titles, vec!["First", "Second", "Third"]);
+        assert!(scanner.games.iter().all(|g| g.emulator == "ryujinx"));
+
+        // Modified in place
+        fs::write(root.join("First.nsp"), b"PFS0").unwrap();
+        let change = scanner.update_paths(&[root.join("First.nsp")]);
+        assert_eq!(change.updated, vec![first_id.clone()]);
+        assert!(scanner.games[0].error.is_some());
+
+        // Deleted file and folder
+        fs::remove_file(root.join("First.nsp")).unwrap();
+        fs::remove_dir_all(root.join("nested")).unwrap();
+        let change = scanner.update_paths(&[root.join("First.nsp"), root.join("nested")]);
+        assert_eq!(change.removed.len(), 2);
+        assert!(change.removed.contains(&first_id));
+        assert_eq!(scanner.games.len(), 1);
+
+        // Outside every root
+        fs::write(home.path().join("Elsewhere.nsp"), b"").unwrap();
+        assert!(scanner
+            .update_paths(&[home.path().join("Elsewhere.nsp")])
+            .is_empty());
+    }
+
+    #[test]
+    fn test_incremental_scan() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().join("Games");
+        fs::create_dir_all(&root).unwrap();
+        fs::write(root.join("First.nsp"), b"").unwrap();
+        fs::write(root.join("Second.nro"), b"").unwrap();
+        let cache_path = dir.path().join(crate::scan_cache::SCAN_CACHE_FILE);
+
+        let scan = |emulator: &str| {
+            let mut scanner = GameScanner::new();
+            scanner.set_scan_cache(ScanCache::load(cache_path.clone()));
+            let mut scan = scanner.start_scan(ScanMonitor::new(Arc::default(), |_| {}));
+            scan.scan_roots(&[scan_root(&root, emulator, 1)]).unwrap();
+            let stats = scanner.finish_scan(scan);
+            (scanner, stats)
+        };
+
+        let (_, stats) = scan("ryujinx");
+        assert_eq!((stats.reused, stats.parsed, stats.removed), (0, 2, 0));
+
+        // Cached entries keep the emulator of the scan that finds them
+        let (scanner, stats) = scan("yuzu");
+        assert_eq!((stats.reused, stats.parsed, stats.removed), (2, 0, 0));
+        assert!(scanner.games.iter().all(|g| g.emulator == "yuzu"));
+        assert!(scanner.games.iter().any(|g| g.error.is_some()));
+
+        fs::write(root.join("First.nsp"), b"PFS0").unwrap();
+        fs::remove_file(root.join("Second.nro")).unwrap();
+        let (mut scanner, stats) = scan("yuzu");
+        assert_eq!((stats.reused, stats.parsed, stats.removed), (0, 1, 1));
+
+        // A watcher event for a file that didn't change is not an update
+        assert!(scanner.update_paths(&[root.join("First.nsp")]).is_empty());
+    }
+
+    #[test]
+    fn test_scan_monitor() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("First.nsp"), b"").unwrap();
+        fs::write(dir.path().join("readme.txt"), b"").unwrap();
+
+        let reports = Arc::new(Mutex::new(Vec::new()));
+        let cancelled = Arc::new(AtomicBool::new(false));
+        let monitor = {
+            let reports = reports.clone();
+            ScanMonitor::new(cancelled.clone(), move |p| {
+                reports.lock().unwrap().push(p.clone())
+            })
+        };
+
+        let mut scanner = GameScanner::new();
+        scanner.set_scan_cache(ScanCache::default());
+        let mut scan = scanner.start_scan(monitor);
+        let roots = [scan_root(dir.path(), "yuzu", 1)];
+        scan.scan_roots(&roots).unwrap();
+        assert_eq!(scan.games.len(), 1);
+        let progress = scan.monitor.as_ref().unwrap().progress();
+        assert_eq!(progress.games_found, 1);
+        // The folder itself and both files
+        assert_eq!(progress.files_seen, 3);
+        assert_eq!(
+            reports.lock().unwrap()[0].root,
+            dir.path().to_string_lossy()
+        );
+
+        cancelled.store(true, Ordering::Relaxed);
+        assert_eq!(scan.scan_roots(&roots), Err(SCAN_CANCELLED.to_string()));
+        scanner.abandon_scan(scan);
+        assert!(scanner.games.is_empty());
+    }
+
+    #[test]
+    fn test_changes_during_scan() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().join("Games");
+        fs::create_dir_all(&root).unwrap();
+        fs::write(root.join("First.nsp"), b"").unwrap();
+        fs::write(dir.path().join("Manual.nsp"), b"").unwrap();
+        let roots = [scan_root(&root, "yuzu", 1)];
+
+        let mut scanner = GameScanner::new();
+        scanner.scan_roots(&roots).unwrap();
+
+        let mut scan = scanner.start_scan(ScanMonitor::new(Arc::default(), |_| {}));
+        scan.scan_roots(&roots).unwrap();
+
+        // Found by the watcher and added by hand after the scan walked
+        fs::write(root.join("Second.nsp"), b"").unwrap();
+        assert_eq!(
+            scanner.update_paths(&[root.join("Second.nsp")]).added.len(),
+            1
+        );
+        scanner
+            .add_game(
+                "Manual".to_string(),
+                dir.path().join("Manual.nsp"),
+                "yuzu".to_string(),
+            )
+            .unwrap();
+
+        scanner.finish_scan(scan);
+        let titles: Vec<_> = scanner.games.iter().map(|g| g.title.as_str()).collect();
+        assert_eq!(titles, vec!["First", "Manual", "Second"]);
+
+        // Only changes made during a scan are redone
+        let scan = scanner.start_scan(ScanMonitor::new(Arc::default(), |_| {}));
+        scanner.abandon_scan(scan);
+        assert!(scanner.pending.is_none());
+    }
+
+    #[test]
+    fn test_launch_unknown_emulator() {
+        let game = Game {
+            emulator: "cemu".to_string(),
+            ..Default::default()
+        };
+        assert_eq!(
+            launch_game_process(&game, &[], &LaunchOverrides::default(), None).unwrap_err(),
+            "Unknown emulator: cemu"
+        );
+
+        // An override picks the profile instead of the scanned emulator
+        let game = Game {
+            emulator: "yuzu".to_string(),
+            ..Default::default()
+        };
+        let overrides = LaunchOverrides {
+            emulator: Some("cemu".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(
+            launch_game_process(&game, &[], &overrides, None).unwrap_err(),
+            "Unknown emulator: cemu"
+        );
+    }
+
+    #[test]
+    fn test_localize_fallback() {
+        let title = |language: &str, name: &str| LocalizedTitle {
+            language: language.to_string(),
+            name: name.to_string(),
+            publisher: String::new(),
+        };
+        let mut game = Game {
+            title: "file name".to_string(),
+            titles: vec![
+                title("BritishEnglish", "Colour"),
+                title("French", "Couleur"),
+            ],
+            ..Default::default()
+        };
+
+        game.localize(&["German".to_string()]);
+        assert_eq!(game.title, "Colour");
+        game.localize(&["German".to_string(), "French".to_string()]);
+        assert_eq!(game.title, "Couleur");
+
+        game.titles.remove(0);
+        game.localize(&[]);
+        assert_eq!(game.title, "Couleur");
+    }
+
+    #[test]
+    fn test_invalid_xci_is_flagged() {
+        let dir = tempfile::tempdir().unwrap();
+        let scanner = GameScanner::new();
+
+        let path = dir.path().join("Fake.xci");
+        fs::write(&path, vec![0u8; 0x400]).unwrap();
+        let game = scanner.build_game(&path, "Fake".to_string(), "yuzu");
+        assert!(game.cartridge.is_none());
+        assert!(game.error.unwrap().contains("gamecard"));
+    }
 }
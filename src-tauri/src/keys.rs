@@ -0,0 +1,361 @@
+//! Console keys (`prod.keys` / `title.keys`) needed to decrypt NCAs
+
+use serde::Serialize;
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::emulator::{self, Locations};
+
+/// Number of key generations a keyset can hold
+pub const KEY_GENERATIONS: usize = 0x20;
+
+pub type Key128 = [u8; 16];
+
+/// Error returned when keys cannot be found or parsed
+#[derive(Debug)]
+pub enum KeyError {
+    /// No `prod.keys` in any of the searched directories
+    NotFound { searched: Vec<PathBuf> },
+    /// A key file line could not be parsed
+    Malformed {
+        path: PathBuf,
+        line: usize,
+        reason: String,
+    },
+    /// A key needed for the current operation is not in the keyset
+    Missing(String),
+}
+
+impl fmt::Display for KeyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            KeyError::NotFound { searched } => {
+                write!(f, "prod.keys not found (searched: ")?;
+                for (i, dir) in searched.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{}", dir.display())?;
+                }
+                write!(f, ")")
+            }
+            KeyError::Malformed { path, line, reason } => {
+                write!(f, "{}:{}: {}", path.display(), line, reason)
+            }
+            KeyError::Missing(name) => write!(f, "Key {} is missing from prod.keys", name),
+        }
+    }
+}
+
+impl std::error::Error for KeyError {}
+
+/// Which key area key family an NCA uses
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub enum KeyAreaKeyIndex {
+    Application = 0,
+    Ocean = 1,
+    System = 2,
+}
+
+/// Keys parsed from `prod.keys` and `title.keys`
+#[derive(Debug, Clone, Default)]
+pub struct KeySet {
+    /// AES-XTS key pair used for NCA headers
+    pub header_key: Option<[u8; 32]>,
+    /// `key_area_key_{application,ocean,system}_XX`
+    pub key_area_keys: [[Option<Key128>; KEY_GENERATIONS]; 3],
+    /// `titlekek_XX`, used to decrypt title keys
+    pub titlekeks: [Option<Key128>; KEY_GENERATIONS],
+    /// Encrypted title keys by rights ID
+    pub title_keys: HashMap<Key128, Key128>,
+    pub prod_keys_path: Option<PathBuf>,
+    pub title_keys_path: Option<PathBuf>,
+}
+
+impl KeySet {
+    /// Find and load `prod.keys` (required) and `title.keys` (optional)
+    /// from the first of `dirs` that contains them
+    pub fn load_from_dirs(dirs: &[PathBuf]) -> Result<Self, KeyError> {
+        let prod_keys = dirs
+            .iter()
+            .map(|dir| dir.join("prod.keys"))
+            .find(|path| path.is_file())
+            .ok_or_else(|| KeyError::NotFound {
+                searched: dirs.to_vec(),
+            })?;
+
+        let mut keys = KeySet::default();
+        keys.load_prod_keys(&prod_keys)?;
+
+        // Prefer title.keys next to prod.keys, then any other location
+        let title_keys = prod_keys
+            .parent()
+            .map(|dir| dir.join("title.keys"))
+            .into_iter()
+            .chain(dirs.iter().map(|dir| dir.join("title.keys")))
+            .find(|path| path.is_file());
+        if let Some(path) = title_keys {
+            keys.load_title_keys(&path)?;
+        }
+
+        Ok(keys)
+    }
+
+    /// Parse a `prod.keys` file, keeping the keys this app uses
+    pub fn load_prod_keys(&mut self, path: &Path) -> Result<(), KeyError> {
+        for (line, name, value) in read_key_file(path)? {
+            let malformed = |reason: String| KeyError::Malformed {
+                path: path.to_path_buf(),
+                line,
+                reason,
+            };
+
+            if name == "header_key" {
+                self.header_key = Some(value.try_into().map_err(|v: Vec<u8>| {
+                    malformed(format!("header_key must be 32 bytes, got {}", v.len()))
+                })?);
+                continue;
+            }
+
+            let (slots, index) = if let Some(index) = name.strip_prefix("titlekek_") {
+                (&mut self.titlekeks, index)
+            } else if let Some(rest) = name.strip_prefix("key_area_key_") {
+                let (family, index) = rest.split_once('_').unwrap_or((rest, ""));
+                let family = match family {
+                    "application" => KeyAreaKeyIndex::Application,
+                    "ocean" => KeyAreaKeyIndex::Ocean,
+                    "system" => KeyAreaKeyIndex::System,
+                    _ => continue,
+                };
+                (&mut self.key_area_keys[family as usize], index)
+            } else {
+                // Other keys are not needed to read metadata
+                continue;
+            };
+
+            // `titlekek_source` and the like aren't per generation
+            let Ok(index) = usize::from_str_radix(index, 16) else {
+                continue;
+            };
+            let Some(slot) = slots.get_mut(index) else {
+                return Err(malformed(format!("Bad key generation in {}", name)));
+            };
+            *slot = Some(value.try_into().map_err(|v: Vec<u8>| {
+                malformed(format!("{} must be 16 bytes, got {}", name, v.len()))
+            })?);
+        }
+
+        if self.header_key.is_none() {
+            return Err(KeyError::Missing("header_key".to_string()));
+        }
+
+        self.prod_keys_path = Some(path.to_path_buf());
+        Ok(())
+    }
+
+    /// Parse a `title.keys` file of `rights_id = title_key` lines
+    pub fn load_title_keys(&mut self, path: &Path) -> Result<(), KeyError> {
+        for (line, name, value) in read_key_file(path)? {
+            let malformed = |reason: &str| KeyError::Malformed {
+                path: path.to_path_buf(),
+                line,
+                reason: reason.to_string(),
+            };
+
+            let rights_id: Key128 = parse_hex(&name)
+                .and_then(|v| v.try_into().ok())
+                .ok_or_else(|| malformed("Rights ID must be 16 bytes of hex"))?;
+            let title_key: Key128 = value
+                .try_into()
+                .map_err(|_| malformed("Title key must be 16 bytes"))?;
+            self.title_keys.insert(rights_id, title_key);
+        }
+
+        self.title_keys_path = Some(path.to_path_buf());
+        Ok(())
+    }
+
+    /// The NCA header key, or an error naming it
+    pub fn header_key(&self) -> Result<&[u8; 32], KeyError> {
+        self.header_key
+            .as_ref()
+            .ok_or_else(|| KeyError::Missing("header_key".to_string()))
+    }
+}
+
+/// Summary of a loaded keyset for the UI
+#[derive(Debug, Serialize, Clone)]
+pub struct KeysInfo {
+    pub prod_keys: Option<String>,
+    pub title_keys: Option<String>,
+    pub title_key_count: usize,
+}
+
+impl KeySet {
+    pub fn info(&self) -> KeysInfo {
+        KeysInfo {
+            prod_keys: self
+                .prod_keys_path
+                .as_ref()
+                .map(|p| p.to_string_lossy().to_string()),
+            title_keys: self
+                .title_keys_path
+                .as_ref()
+                .map(|p| p.to_string_lossy().to_string()),
+            title_key_count: self.title_keys.len(),
+        }
+    }
+}
+
+/// Load keys from the emulator key directories in `locations`
+pub fn load_keys_in(locations: &Locations) -> Result<KeySet, KeyError> {
+    KeySet::load_from_dirs(&key_search_dirs(locations))
+}
+
+/// Directories where emulators keep their key files
+pub fn key_search_dirs(locations: &Locations) -> Vec<PathBuf> {
+    emulator::all()
+        .iter()
+        .flat_map(|emulator| emulator.key_dirs(locations))
+        // hactool and friends
+        .chain(std::iter::once(locations.home_dir.join(".switch")))
+        .collect()
+}
+
+/// Read `name = hex` lines, skipping blanks and comments. Returns the
+/// 1-based line number, lowercased name and decoded value of each.
+fn read_key_file(path: &Path) -> Result<Vec<(usize, String, Vec<u8>)>, KeyError> {
+    let contents = fs::read_to_string(path).map_err(|e| KeyError::Malformed {
+        path: path.to_path_buf(),
+        line: 0,
+        reason: e.to_string(),
+    })?;
+
+    let mut entries = Vec::new();
+    for (i, line) in contents.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with(';') || line.starts_with('#') {
+            continue;
+        }
+
+        let malformed = |reason: &str| KeyError::Malformed {
+            path: path.to_path_buf(),
+            line: i + 1,
+            reason: reason.to_string(),
+        };
+
+        let (name, value) = line
+            .split_once('=')
+            .ok_or_else(|| malformed("Expected name = value"))?;
+        let value = parse_hex(value.trim()).ok_or_else(|| malformed("Value is not hex"))?;
+        entries.push((i + 1, name.trim().to_ascii_lowercase(), value));
+    }
+
+    Ok(entries)
+}
+
+/// Decode a hex string; `None` if it has odd length or non-hex characters
+pub fn parse_hex(s: &str) -> Option<Vec<u8>> {
+    if s.len() % 2 == 1 {
+        return None;
+    }
+    (0..s.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(s.get(i..i + 2)?, 16).ok())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PROD_KEYS: &str = "\
+; comment
+header_key = 00112233445566778899aabbccddeeff00112233445566778899aabbccddeeff
+key_area_key_application_00 = 000102030405060708090a0b0c0d0e0f
+key_area_key_system_0a = 0f0e0d0c0b0a09080706050403020100
+titlekek_01 = ffeeddccbbaa99887766554433221100
+master_key_00 = 00000000000000000000000000000000
+";
+
+    #[test]
+    fn test_load_keys() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("prod.keys"), PROD_KEYS).unwrap();
+        fs::write(
+            dir.path().join("title.keys"),
+            "01007ef00011e0000000000000000004 = 11111111111111111111111111111111\n",
+        )
+        .unwrap();
+
+        let keys = KeySet::load_from_dirs(&[dir.path().to_path_buf()]).unwrap();
+        assert_eq!(keys.header_key().unwrap()[1], 0x11);
+        assert_eq!(keys.key_area_keys[0][0].unwrap()[15], 0x0f);
+        assert_eq!(keys.key_area_keys[2][0x0a].unwrap()[0], 0x0f);
+        assert_eq!(keys.titlekeks[1].unwrap()[0], 0xff);
+        assert_eq!(keys.title_keys.len(), 1);
+    }
+
+    #[test]
+    fn test_source_keys() {
+        // As dumped by Lockpick_RCM, with the sources of the derived keys
+        let prod_keys = "\
+aes_kek_generation_source = 4d870986c45d20722fba1053da92e8a9
+header_kek_source = 1f12913a4acbf00d4cde3af6d523882a
+header_key = 00112233445566778899aabbccddeeff00112233445566778899aabbccddeeff
+key_area_key_application_00 = 000102030405060708090a0b0c0d0e0f
+key_area_key_application_source = 7f59971e629f36a13098066f2144c30d
+key_area_key_ocean_source = 327d36085ad1758dab4e6fbaa555d882
+key_area_key_system_source = 8745f1bba6be79647d048ba67b5fda4a
+master_key_00 = 00000000000000000000000000000000
+titlekek_00 = ffeeddccbbaa99887766554433221100
+titlekek_source = 1edc7b3b60e6b4d878b81715985e629b
+";
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("prod.keys"), prod_keys).unwrap();
+        let keys = KeySet::load_from_dirs(&[dir.path().to_path_buf()]).unwrap();
+        assert_eq!(keys.titlekeks[0].unwrap()[0], 0xff);
+        assert_eq!(keys.key_area_keys[0][0].unwrap()[1], 0x01);
+
+        // A generation past the last one is an error
+        fs::write(
+            dir.path().join("prod.keys"),
+            format!(
+                "{}titlekek_ff = ffeeddccbbaa99887766554433221100\n",
+                prod_keys
+            ),
+        )
+        .unwrap();
+        let result = KeySet::load_from_dirs(&[dir.path().to_path_buf()]);
+        assert!(matches!(result, Err(KeyError::Malformed { line: 11, .. })));
+    }
+
+    #[test]
+    fn test_missing_keys() {
+        let dir = tempfile::tempdir().unwrap();
+        let result = KeySet::load_from_dirs(&[dir.path().to_path_buf()]);
+        assert!(matches!(result, Err(KeyError::NotFound { .. })));
+
+        fs::write(
+            dir.path().join("prod.keys"),
+            "titlekek_00 = 00000000000000000000000000000000\n",
+        )
+        .unwrap();
+        let result = KeySet::load_from_dirs(&[dir.path().to_path_buf()]);
+        assert!(matches!(result, Err(KeyError::Missing(_))));
+    }
+
+    #[test]
+    fn test_malformed_keys() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("prod.keys"), "header_key = 0011\n").unwrap();
+        let result = KeySet::load_from_dirs(&[dir.path().to_path_buf()]);
+        assert!(matches!(result, Err(KeyError::Malformed { line: 1, .. })));
+
+        fs::write(dir.path().join("prod.keys"), "header_key = zz\n").unwrap();
+        let result = KeySet::load_from_dirs(&[dir.path().to_path_buf()]);
+        assert!(matches!(result, Err(KeyError::Malformed { .. })));
+    }
+}
@@ -1,32 +1,276 @@
-use std::sync::Mutex;
-use tauri::State;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, PoisonError};
+use tauri::{AppHandle, Emitter, Manager, State};
 
+mod emulator;
+mod formats;
 mod games;
-use games::{Game, GameScanner};
+mod keys;
+mod library;
+mod process;
+mod qsettings;
+mod scan_cache;
+mod scan_config;
+mod session_log;
+mod settings;
+mod store;
+mod watcher;
+use emulator::{EmulatorProfile, LaunchOverrides, Locations};
+use formats::nacp;
+use games::{Game, GameScanner, ScanMonitor, ScanResult};
+use library::{GameEdit, Library, PlayStats, Session};
+use process::{RunningGame, RunningGameInfo};
+use scan_cache::ScanCache;
+use scan_config::{ScanConfig, ScanFolder};
+use session_log::SessionLog;
+use settings::{Settings, SettingsStore};
+use watcher::LibraryWatcher;
 
 struct AppState {
     scanner: Mutex<GameScanner>,
-    running_game: Mutex<Option<std::process::Child>>,
+    library: Mutex<Library>,
+    settings: Mutex<SettingsStore>,
+    running_game: Mutex<Option<RunningGame>>,
+    /// Cancellation flag of the scan in progress, if any
+    scan: Mutex<Option<Arc<AtomicBool>>>,
+    /// Follows the scanned folders once a scan has run
+    watcher: Mutex<Option<LibraryWatcher>>,
+    /// Where emulator output is logged per session
+    log_dir: std::path::PathBuf,
 }
 
+/// Library games localized to the user's preferred languages
+fn localized_games(state: &AppState, library: &Library) -> Result<Vec<Game>, String> {
+    let settings = state.settings.lock().map_err(|e| e.to_string())?;
+    Ok(library.games(&settings.settings.preferred_languages))
+}
+
+fn remote_icons_enabled(state: &AppState) -> Result<bool, String> {
+    let settings = state.settings.lock().map_err(|e| e.to_string())?;
+    Ok(settings.settings.remote_icons)
+}
+
+/// Scan the user's folders and every emulator's game folders on a
+/// background thread, emitting
+/// `scan-progress` as it goes. The library keeps its previous games until
+/// the scan completes. Files unchanged since the last scan are taken
+/// from the scan cache instead of being parsed again.
+#[tauri::command]
+async fn scan_games(app: AppHandle) -> Result<ScanResult, String> {
+    tauri::async_runtime::spawn_blocking(move || run_scan(&app))
+        .await
+        .map_err(|e| e.to_string())?
+}
+
+/// Stop the scan in progress. Returns whether there was one.
 #[tauri::command]
-fn scan_games(state: State<AppState>) -> Result<Vec<Game>, String> {
+fn cancel_scan(state: State<AppState>) -> Result<bool, String> {
+    let scan = state.scan.lock().map_err(|e| e.to_string())?;
+    match scan.as_ref() {
+        Some(cancelled) => {
+            cancelled.store(true, Ordering::Relaxed);
+            Ok(true)
+        }
+        None => Ok(false),
+    }
+}
+
+fn run_scan(app: &AppHandle) -> Result<ScanResult, String> {
+    let state = app.state::<AppState>();
+    let cancelled = Arc::new(AtomicBool::new(false));
+    {
+        let mut scan = state.scan.lock().map_err(|e| e.to_string())?;
+        if scan.is_some() {
+            return Err("A scan is already running".to_string());
+        }
+        *scan = Some(cancelled.clone());
+    }
+
+    let _slot = ScanSlot(&state.scan);
+    scan_all(app, &state, cancelled)
+}
+
+/// Frees the running scan slot however `scan_all` exits
+struct ScanSlot<'a>(&'a Mutex<Option<Arc<AtomicBool>>>);
+
+impl Drop for ScanSlot<'_> {
+    fn drop(&mut self) {
+        *self.0.lock().unwrap_or_else(PoisonError::into_inner) = None;
+    }
+}
+
+fn scan_all(
+    app: &AppHandle,
+    state: &AppState,
+    cancelled: Arc<AtomicBool>,
+) -> Result<ScanResult, String> {
+    let (remote_icons, config) = {
+        let settings = state.settings.lock().map_err(|e| e.to_string())?;
+        (
+            settings.settings.remote_icons,
+            settings.settings.scan.clone(),
+        )
+    };
+    let progress_app = app.clone();
+    let monitor = ScanMonitor::new(cancelled, move |progress| {
+        if let Err(e) = progress_app.emit("scan-progress", progress) {
+            log::warn!("Failed to emit scan-progress: {}", e);
+        }
+    });
+
+    // Walk without holding the scanner so watcher updates and manual
+    // additions aren't stuck behind the scan
+    let mut scan = state
+        .scanner
+        .lock()
+        .map_err(|e| e.to_string())?
+        .start_scan(monitor);
+    // A panic in a parser must still hand the cache back to the scanner
+    let walked = panic::catch_unwind(AssertUnwindSafe(|| {
+        scan.set_remote_icons(remote_icons);
+        if let Some(home_dir) = dirs::home_dir() {
+            scan.set_locations(Locations::find(&home_dir, config.builtin_locations));
+        }
+        scan.set_scan_config(config)?;
+        if let Err(e) = scan.load_keys() {
+            log::warn!("Scanning without keys: {}", e);
+        }
+        scan.scan_emulators(emulator::all())
+    }))
+    .unwrap_or_else(|_| Err("Scan failed unexpectedly".to_string()));
+
+    let (result, roots) = {
+        let mut scanner = state.scanner.lock().map_err(|e| e.to_string())?;
+        if let Err(e) = walked {
+            scanner.abandon_scan(scan);
+            return Err(e);
+        }
+        let stats = scanner.finish_scan(scan);
+        log::info!(
+            "Scanned {} games: {} reused, {} parsed, {} removed",
+            scanner.games.len(),
+            stats.reused,
+            stats.parsed,
+            stats.removed
+        );
+
+        let mut library = state.library.lock().map_err(|e| e.to_string())?;
+        library.set_scanned_games(scanner.get_games());
+        library.save()?;
+        let result = ScanResult {
+            games: localized_games(state, &library)?,
+            stats,
+        };
+        (result, scanner.roots().to_vec())
+    };
+
+    // Not under the scanner lock, which watcher callbacks take
+    let mut watcher = state.watcher.lock().map_err(|e| e.to_string())?;
+    if let Some(watcher) = watcher.as_mut() {
+        watcher.watch(&roots);
+    }
+    Ok(result)
+}
+
+/// Update the games under `paths` after the watcher saw them change and
+/// emit `library-changed` with the IDs that were added, removed or updated
+fn on_paths_changed(app: &AppHandle, paths: &[std::path::PathBuf]) -> Result<(), String> {
+    let state = app.state::<AppState>();
+    // Before taking the scanner, whose lock the watcher must not be used under
+    if let Some(watcher) = state.watcher.lock().map_err(|e| e.to_string())?.as_mut() {
+        watcher.watch_new_dirs(paths);
+    }
+
     let mut scanner = state.scanner.lock().map_err(|e| e.to_string())?;
+    let change = scanner.update_paths(paths);
+    if change.is_empty() {
+        return Ok(());
+    }
 
-    // Reset before scanning to avoid duplicates across scans
-    scanner.reset();
+    let mut library = state.library.lock().map_err(|e| e.to_string())?;
+    library.set_scanned_games(scanner.get_games());
+    library.save()?;
+    app.emit("library-changed", change)
+        .map_err(|e| format!("Failed to emit library-changed: {}", e))
+}
 
-    // Scan both emulators
-    scanner.scan_yuzu()?;
-    scanner.scan_ryujinx()?;
+/// Validate and save an edited scan config. It applies from the next scan.
+fn edit_scan_config(
+    state: &AppState,
+    edit: impl FnOnce(&mut ScanConfig),
+) -> Result<Settings, String> {
+    let mut settings = state.settings.lock().map_err(|e| e.to_string())?;
+    let mut config = settings.settings.scan.clone();
+    edit(&mut config);
+    config.validate()?;
+    settings.settings.scan = config;
+    settings.save()?;
+    Ok(settings.settings.clone())
+}
 
-    Ok(scanner.get_games())
+#[tauri::command]
+fn get_scan_config(state: State<AppState>) -> Result<ScanConfig, String> {
+    let settings = state.settings.lock().map_err(|e| e.to_string())?;
+    Ok(settings.settings.scan.clone())
+}
+
+/// Add a folder to scan, replacing any existing entry for the same path
+#[tauri::command]
+fn add_scan_folder(
+    state: State<AppState>,
+    path: String,
+    emulator: String,
+    max_depth: Option<usize>,
+) -> Result<Settings, String> {
+    {
+        let settings = state.settings.lock().map_err(|e| e.to_string())?;
+        if !emulator::is_known(&emulator, &settings.settings.emulator_profiles) {
+            return Err(format!("Unknown emulator: {}", emulator));
+        }
+    }
+
+    let folder = ScanFolder {
+        path: std::path::PathBuf::from(path),
+        emulator,
+        max_depth: max_depth.unwrap_or(emulator::DEFAULT_SCAN_DEPTH),
+    };
+    edit_scan_config(&state, |config| {
+        match config.folders.iter_mut().find(|f| f.path == folder.path) {
+            Some(existing) => *existing = folder,
+            None => config.folders.push(folder),
+        }
+    })
+}
+
+#[tauri::command]
+fn remove_scan_folder(state: State<AppState>, path: String) -> Result<Settings, String> {
+    let path = std::path::PathBuf::from(path);
+    edit_scan_config(&state, |config| config.folders.retain(|f| f.path != path))
+}
+
+/// Replace the glob patterns of paths that scans skip
+#[tauri::command]
+fn set_scan_excludes(state: State<AppState>, excludes: Vec<String>) -> Result<Settings, String> {
+    edit_scan_config(&state, |config| config.excludes = excludes)
+}
+
+#[tauri::command]
+fn set_follow_symlinks(state: State<AppState>, enabled: bool) -> Result<Settings, String> {
+    edit_scan_config(&state, |config| config.follow_symlinks = enabled)
+}
+
+/// Turn the common game folders and the Downloads search for portable
+/// emulators on or off
+#[tauri::command]
+fn set_builtin_locations(state: State<AppState>, enabled: bool) -> Result<Settings, String> {
+    edit_scan_config(&state, |config| config.builtin_locations = enabled)
 }
 
 #[tauri::command]
 fn get_games(state: State<AppState>) -> Result<Vec<Game>, String> {
-    let scanner = state.scanner.lock().map_err(|e| e.to_string())?;
-    Ok(scanner.get_games())
+    let library = state.library.lock().map_err(|e| e.to_string())?;
+    localized_games(&state, &library)
 }
 
 #[tauri::command]
@@ -36,33 +280,433 @@ fn add_game(
     path: String,
     emulator: String,
 ) -> Result<Game, String> {
+    {
+        let settings = state.settings.lock().map_err(|e| e.to_string())?;
+        if !emulator::is_known(&emulator, &settings.settings.emulator_profiles) {
+            return Err(format!("Unknown emulator: {}", emulator));
+        }
+    }
+
     let mut scanner = state.scanner.lock().map_err(|e| e.to_string())?;
-    scanner.add_game(title, std::path::PathBuf::from(path), emulator)
+    scanner.set_remote_icons(remote_icons_enabled(&state)?);
+    let game = scanner.add_game(title, std::path::PathBuf::from(path), emulator)?;
+
+    let mut library = state.library.lock().map_err(|e| e.to_string())?;
+    library.add_manual_game(game.clone());
+    library.save()?;
+
+    Ok(game)
 }
 
 #[tauri::command]
-fn launch_game_cmd(state: State<AppState>, game: Game) -> Result<(), String> {
-    let mut running = state.running_game.lock().map_err(|e| e.to_string())?;
+fn edit_game(
+    state: State<AppState>,
+    id: String,
+    title: Option<String>,
+    icon: Option<String>,
+) -> Result<Vec<Game>, String> {
+    let mut library = state.library.lock().map_err(|e| e.to_string())?;
+    library.edit_game(&id, GameEdit { title, icon })?;
+    library.save()?;
+    localized_games(&state, &library)
+}
 
-    if let Some(child) = running.as_mut() {
-        match child.try_wait() {
-            Ok(Some(_status)) => {
-                *running = None;
-            }
-            Ok(None) => {
-                return Err("Game already running".to_string());
+#[tauri::command]
+fn get_launch_overrides(state: State<AppState>, id: String) -> Result<LaunchOverrides, String> {
+    let library = state.library.lock().map_err(|e| e.to_string())?;
+    Ok(library.launch_overrides(&id))
+}
+
+#[tauri::command]
+fn set_launch_overrides(
+    state: State<AppState>,
+    id: String,
+    overrides: LaunchOverrides,
+) -> Result<LaunchOverrides, String> {
+    let mut library = state.library.lock().map_err(|e| e.to_string())?;
+    {
+        // Validate against the emulator the game would actually launch with
+        let game = library.game(&id).ok_or("Game not found")?;
+        let emulator = overrides.emulator.as_deref().unwrap_or(&game.emulator);
+        let settings = state.settings.lock().map_err(|e| e.to_string())?;
+        let profile = emulator::unresolved_profile(emulator, &settings.settings.emulator_profiles)
+            .ok_or_else(|| format!("Unknown emulator: {}", emulator))?;
+        profile.with_overrides(&overrides).validate()?;
+    }
+    library.set_launch_overrides(&id, overrides)?;
+    library.save()?;
+    Ok(library.launch_overrides(&id))
+}
+
+/// Emulator locations from the last scan. Before the first, they are
+/// found now and kept for the scanner.
+fn emulator_locations(state: &AppState) -> Result<Option<Locations>, String> {
+    if let Some(locations) = state.scanner.lock().map_err(|e| e.to_string())?.locations() {
+        return Ok(Some(locations.clone()));
+    }
+
+    let search_downloads = {
+        let settings = state.settings.lock().map_err(|e| e.to_string())?;
+        settings.settings.scan.builtin_locations
+    };
+    let Some(home_dir) = dirs::home_dir() else {
+        return Ok(None);
+    };
+    // Found without holding the scanner, since it walks Downloads
+    let locations = Locations::find(&home_dir, search_downloads);
+    let mut scanner = state.scanner.lock().map_err(|e| e.to_string())?;
+    if scanner.locations().is_none() {
+        scanner.set_locations(locations.clone());
+    }
+    Ok(Some(locations))
+}
+
+/// Mark a game as a favorite. With `sync_favorites` on, the emulator's
+/// own favorites are updated too where it keeps any for the title.
+#[tauri::command]
+fn set_favorite(state: State<AppState>, id: String, favorite: bool) -> Result<Vec<Game>, String> {
+    let sync = {
+        let settings = state.settings.lock().map_err(|e| e.to_string())?;
+        settings.settings.sync_favorites
+    };
+
+    let games = {
+        let mut library = state.library.lock().map_err(|e| e.to_string())?;
+        library.set_favorite(&id, favorite)?;
+        library.save()?;
+        localized_games(&state, &library)?
+    };
+
+    if sync {
+        let game = games.iter().find(|g| g.id == id);
+        let target = game.and_then(|g| Some((emulator::get(&g.emulator)?, g.title_id.as_ref()?)));
+        if let (Some((emulator, title_id)), Some(locations)) = (target, emulator_locations(&state)?)
+        {
+            if let Err(e) = emulator.set_favorite(&locations, title_id, favorite) {
+                log::warn!("Failed to update {} favorites: {}", emulator.name(), e);
             }
+        }
+    }
+
+    Ok(games)
+}
+
+#[tauri::command]
+fn set_sync_favorites(state: State<AppState>, enabled: bool) -> Result<Settings, String> {
+    let mut settings = state.settings.lock().map_err(|e| e.to_string())?;
+    settings.settings.sync_favorites = enabled;
+    settings.save()?;
+    Ok(settings.settings.clone())
+}
+
+/// Playtime totals for every played game, most recently played first
+#[tauri::command]
+fn get_play_stats(state: State<AppState>) -> Result<Vec<PlayStats>, String> {
+    let library = state.library.lock().map_err(|e| e.to_string())?;
+    Ok(library.play_stats(process::unix_time()))
+}
+
+#[tauri::command]
+fn get_play_sessions(state: State<AppState>, id: String) -> Result<Vec<Session>, String> {
+    let library = state.library.lock().map_err(|e| e.to_string())?;
+    Ok(library.sessions(&id))
+}
+
+/// Emulator output of a session, as named by `Session::log` or
+/// `RunningGameInfo::log`. With `lines`, only the last that many lines.
+#[tauri::command]
+fn get_session_log(
+    state: State<AppState>,
+    log: String,
+    lines: Option<usize>,
+) -> Result<String, String> {
+    let path = session_log::log_path(&state.log_dir, &log)?;
+    session_log::tail(&path, lines).map_err(|e| format!("Failed to read {}: {}", log, e))
+}
+
+#[tauri::command]
+fn get_settings(state: State<AppState>) -> Result<Settings, String> {
+    let settings = state.settings.lock().map_err(|e| e.to_string())?;
+    Ok(settings.settings.clone())
+}
+
+#[tauri::command]
+fn set_preferred_languages(
+    state: State<AppState>,
+    languages: Vec<String>,
+) -> Result<Vec<Game>, String> {
+    if let Some(unknown) = languages
+        .iter()
+        .find(|l| !nacp::LANGUAGES.contains(&l.as_str()))
+    {
+        return Err(format!("Unknown language: {}", unknown));
+    }
+
+    {
+        let mut settings = state.settings.lock().map_err(|e| e.to_string())?;
+        settings.settings.preferred_languages = languages;
+        settings.save()?;
+    }
+
+    let library = state.library.lock().map_err(|e| e.to_string())?;
+    localized_games(&state, &library)
+}
+
+#[tauri::command]
+fn set_remote_icons(state: State<AppState>, enabled: bool) -> Result<Settings, String> {
+    let mut settings = state.settings.lock().map_err(|e| e.to_string())?;
+    settings.settings.remote_icons = enabled;
+    settings.save()?;
+    Ok(settings.settings.clone())
+}
+
+#[tauri::command]
+fn get_emulators(state: State<AppState>) -> Result<Vec<emulator::EmulatorInfo>, String> {
+    let settings = state.settings.lock().map_err(|e| e.to_string())?;
+    Ok(emulator::list(&settings.settings.emulator_profiles))
+}
+
+/// Add a profile, replacing any existing one with the same ID
+#[tauri::command]
+fn save_emulator_profile(
+    state: State<AppState>,
+    profile: EmulatorProfile,
+) -> Result<Settings, String> {
+    profile.validate()?;
+
+    let mut settings = state.settings.lock().map_err(|e| e.to_string())?;
+    let profiles = &mut settings.settings.emulator_profiles;
+    match profiles
+        .iter_mut()
+        .find(|p| p.id.eq_ignore_ascii_case(&profile.id))
+    {
+        Some(existing) => *existing = profile,
+        None => profiles.push(profile),
+    }
+    settings.save()?;
+    Ok(settings.settings.clone())
+}
+
+#[tauri::command]
+fn delete_emulator_profile(state: State<AppState>, id: String) -> Result<Settings, String> {
+    let mut settings = state.settings.lock().map_err(|e| e.to_string())?;
+    settings
+        .settings
+        .emulator_profiles
+        .retain(|p| !p.id.eq_ignore_ascii_case(&id));
+    settings.save()?;
+    Ok(settings.settings.clone())
+}
+
+#[tauri::command]
+fn check_keys(state: State<AppState>) -> Result<keys::KeysInfo, String> {
+    let locations = emulator_locations(&state)?.ok_or("No home directory")?;
+    keys::load_keys_in(&locations)
+        .map(|keys| keys.info())
+        .map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+fn inspect_game(state: State<AppState>, path: String) -> Result<Vec<games::NcaInfo>, String> {
+    emulator_locations(&state)?;
+    let mut scanner = state.scanner.lock().map_err(|e| e.to_string())?;
+    scanner.load_keys().map_err(|e| e.to_string())?;
+    scanner.inspect_ncas(std::path::Path::new(&path))
+}
+
+/// Check whether the running game has exited. If it has, clear it, record
+/// the session and emit `game-exited`, plus `game-crashed` if it ended
+/// abnormally. Returns whether a game is still running.
+fn reap_game(app: &AppHandle, running: &mut Option<RunningGame>) -> Result<bool, String> {
+    let Some(game) = running.as_mut() else {
+        return Ok(false);
+    };
+    let Some(exit) = game.poll()? else {
+        return Ok(true);
+    };
+    *running = None;
+
+    log::info!(
+        "{} exited after {}s (code {:?}, signal {:?})",
+        exit.title,
+        exit.duration,
+        exit.code,
+        exit.signal
+    );
+    {
+        let state = app.state::<AppState>();
+        let mut library = state.library.lock().map_err(|e| e.to_string())?;
+        library.record_session(&exit.game_id, Session::from(&exit));
+        if let Err(e) = library.save() {
+            log::warn!("Failed to save play session: {}", e);
+        }
+    }
+    if let Err(e) = app.emit("game-exited", exit.clone()) {
+        log::warn!("Failed to emit game-exited: {}", e);
+    }
+    if exit.crashed {
+        if let Err(e) = app.emit("game-crashed", exit) {
+            log::warn!("Failed to emit game-crashed: {}", e);
+        }
+    }
+    Ok(false)
+}
+
+/// Poll the game with process ID `pid` until it exits
+fn watch_game(app: AppHandle, pid: u32) {
+    loop {
+        std::thread::sleep(process::POLL_INTERVAL);
+
+        let state = app.state::<AppState>();
+        let Ok(mut running) = state.running_game.lock() else {
+            return;
+        };
+        // Another game was launched after this one was reaped
+        if running.as_ref().map(RunningGame::pid) != Some(pid) {
+            return;
+        }
+        match reap_game(&app, &mut running) {
+            Ok(true) => {}
+            Ok(false) => return,
             Err(e) => {
-                return Err(format!("Failed to check running game: {}", e));
+                log::warn!("{}", e);
+                return;
             }
         }
     }
+}
+
+#[tauri::command]
+fn launch_game_cmd(app: AppHandle, state: State<AppState>, game: Game) -> Result<(), String> {
+    // Before taking the running game, since finding them may walk Downloads
+    let locations = emulator_locations(&state)?;
+    let mut running = state.running_game.lock().map_err(|e| e.to_string())?;
+    if reap_game(&app, &mut running)? {
+        return Err("Game already running".to_string());
+    }
+
+    let profiles = {
+        let settings = state.settings.lock().map_err(|e| e.to_string())?;
+        settings.settings.emulator_profiles.clone()
+    };
+    let overrides = {
+        let library = state.library.lock().map_err(|e| e.to_string())?;
+        library.launch_overrides(&game.id)
+    };
+    let child = games::launch_game_process(&game, &profiles, &overrides, locations.as_ref())?;
 
-    let child = games::launch_game_process(&game)?;
-    *running = Some(child);
+    let emulator = overrides.emulator.as_deref().unwrap_or(&game.emulator);
+    let mut running_game = RunningGame::new(&game, emulator, child);
+    session_log::prune(&state.log_dir, session_log::KEEP_LOGS - 1);
+    let log = SessionLog::create(&state.log_dir, &game.id, running_game.started_at())
+        .map_err(|e| log::warn!("Failed to create session log: {}", e))
+        .ok();
+    running_game.capture_output(log.as_ref());
+    let info = running_game.info();
+    *running = Some(running_game);
+
+    if let Err(e) = app.emit("game-started", info.clone()) {
+        log::warn!("Failed to emit game-started: {}", e);
+    }
+    std::thread::spawn(move || watch_game(app, info.pid));
     Ok(())
 }
 
+/// Close the running game: ask the emulator to quit, and kill it with
+/// everything it started if it has not exited after `STOP_TIMEOUT`.
+/// Returns once the emulator was asked; the exit is reported through
+/// `game-exited` like any other.
+#[tauri::command]
+fn stop_game(app: AppHandle, state: State<AppState>) -> Result<(), String> {
+    let pid = {
+        let mut running = state.running_game.lock().map_err(|e| e.to_string())?;
+        if !reap_game(&app, &mut running)? {
+            return Err("No game running".to_string());
+        }
+        let game = running.as_mut().expect("reaped game is running");
+        game.terminate()?;
+        game.pid()
+    };
+
+    std::thread::spawn(move || kill_after_timeout(app, pid));
+    Ok(())
+}
+
+/// Kill the game `pid` if it is still running after `STOP_TIMEOUT`.
+/// `watch_game` notices when it is gone.
+fn kill_after_timeout(app: AppHandle, pid: u32) {
+    std::thread::sleep(process::STOP_TIMEOUT);
+
+    let state = app.state::<AppState>();
+    let Ok(mut running) = state.running_game.lock() else {
+        return;
+    };
+    // Gone, or replaced by a game launched in the meantime
+    if running.as_ref().map(RunningGame::pid) != Some(pid) {
+        return;
+    }
+    match reap_game(&app, &mut running) {
+        Ok(true) => {
+            log::warn!("Game did not exit in time, killing it");
+            let game = running.as_mut().expect("reaped game is running");
+            if let Err(e) = game.kill() {
+                log::warn!("{}", e);
+            }
+        }
+        Ok(false) => {}
+        Err(e) => log::warn!("{}", e),
+    }
+}
+
+#[tauri::command]
+fn suspend_game(state: State<AppState>) -> Result<RunningGameInfo, String> {
+    let mut running = state.running_game.lock().map_err(|e| e.to_string())?;
+    let game = running.as_mut().ok_or("No game running")?;
+    game.suspend()?;
+    Ok(game.info())
+}
+
+#[tauri::command]
+fn resume_game(state: State<AppState>) -> Result<RunningGameInfo, String> {
+    let mut running = state.running_game.lock().map_err(|e| e.to_string())?;
+    let game = running.as_mut().ok_or("No game running")?;
+    game.resume()?;
+    Ok(game.info())
+}
+
+#[tauri::command]
+fn get_running_game(
+    app: AppHandle,
+    state: State<AppState>,
+) -> Result<Option<RunningGameInfo>, String> {
+    let mut running = state.running_game.lock().map_err(|e| e.to_string())?;
+    reap_game(&app, &mut running)?;
+    Ok(running.as_ref().map(RunningGame::info))
+}
+
+/// Entry points for the benchmarks in `benches/`; not used by the app
+#[doc(hidden)]
+pub mod bench {
+    use crate::games::{GameScanner, ScanRoot};
+    use std::path::PathBuf;
+
+    /// Scan `roots` as yuzu game folders without a scan cache, so every
+    /// file is read, and return the number of games found
+    pub fn scan_roots(roots: &[PathBuf], max_depth: usize) -> Result<usize, String> {
+        let roots: Vec<ScanRoot> = roots
+            .iter()
+            .map(|path| ScanRoot {
+                path: path.clone(),
+                emulator: "yuzu".to_string(),
+                max_depth,
+            })
+            .collect();
+        let mut scanner = GameScanner::new();
+        scanner.scan_roots(&roots)?;
+        Ok(scanner.games.len())
+    }
+}
+
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
     tauri::Builder::default()
@@ -74,17 +718,64 @@ pub fn run() {
                         .build(),
                 )?;
             }
+
+            // Load the persisted library so get_games works before any scan
+            let data_dir = app.path().app_data_dir()?;
+            let mut scanner = GameScanner::new();
+            scanner.set_icon_cache(data_dir.join(games::ICON_CACHE_DIR));
+            scanner.set_scan_cache(ScanCache::load(data_dir.join(scan_cache::SCAN_CACHE_FILE)));
+            let handle = app.handle().clone();
+            let watcher = LibraryWatcher::new(move |paths| {
+                if let Err(e) = on_paths_changed(&handle, &paths) {
+                    log::warn!("Failed to update library: {}", e);
+                }
+            })
+            .inspect_err(|e| log::warn!("{}", e))
+            .ok();
+            let settings = SettingsStore::load(data_dir.join(settings::SETTINGS_FILE));
+            app.manage(AppState {
+                scanner: Mutex::new(scanner),
+                library: Mutex::new(Library::load(data_dir.join(library::LIBRARY_FILE))),
+                settings: Mutex::new(settings),
+                running_game: Mutex::new(None),
+                scan: Mutex::new(None),
+                watcher: Mutex::new(watcher),
+                log_dir: data_dir.join(session_log::LOG_DIR),
+            });
             Ok(())
         })
-        .manage(AppState {
-            scanner: Mutex::new(GameScanner::new()),
-            running_game: Mutex::new(None),
-        })
         .invoke_handler(tauri::generate_handler![
             scan_games,
+            cancel_scan,
+            get_scan_config,
+            add_scan_folder,
+            remove_scan_folder,
+            set_scan_excludes,
+            set_follow_symlinks,
+            set_builtin_locations,
             get_games,
             add_game,
-            launch_game_cmd
+            edit_game,
+            get_launch_overrides,
+            set_launch_overrides,
+            set_favorite,
+            set_sync_favorites,
+            get_play_stats,
+            get_play_sessions,
+            get_session_log,
+            get_settings,
+            set_preferred_languages,
+            set_remote_icons,
+            get_emulators,
+            save_emulator_profile,
+            delete_emulator_profile,
+            check_keys,
+            inspect_game,
+            launch_game_cmd,
+            stop_game,
+            suspend_game,
+            resume_game,
+            get_running_game
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
@@ -0,0 +1,544 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+use crate::emulator::LaunchOverrides;
+use crate::games::Game;
+use crate::process::GameExit;
+use crate::store;
+
+/// File name of the library store inside the app data directory
+pub const LIBRARY_FILE: &str = "library.json";
+
+/// Current on-disk schema version. Bump this and add a step to `migrate`
+/// whenever the layout of `LibraryData` changes in a non-additive way.
+pub const LIBRARY_VERSION: u32 = 1;
+
+/// User-made changes to a game that must survive rescans
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+pub struct GameEdit {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub icon: Option<String>,
+}
+
+/// One play session of a game
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct Session {
+    /// Seconds since the Unix epoch
+    pub started_at: u64,
+    pub ended_at: u64,
+    /// Seconds played, not counting time suspended
+    pub duration: u64,
+    pub emulator: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub code: Option<i32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub signal: Option<i32>,
+    #[serde(default)]
+    pub crashed: bool,
+    /// File name of the emulator output log, while it is kept
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub log: Option<String>,
+}
+
+impl From<&GameExit> for Session {
+    fn from(exit: &GameExit) -> Self {
+        Session {
+            started_at: exit.started_at,
+            ended_at: exit.ended_at,
+            duration: exit.duration,
+            emulator: exit.emulator.clone(),
+            code: exit.code,
+            signal: exit.signal,
+            crashed: exit.crashed,
+            log: exit.log.clone(),
+        }
+    }
+}
+
+/// Playtime totals for a game that was played
+#[derive(Debug, Serialize, Clone, PartialEq)]
+pub struct PlayStats {
+    pub game_id: String,
+    /// Seconds played over all sessions, or the emulator's own count if
+    /// higher. Emulators also count sessions started here, so the two
+    /// are not added up.
+    pub total_playtime: u64,
+    /// When the game was last played, in seconds since the Unix epoch
+    pub last_played: u64,
+    pub sessions: usize,
+    /// Sessions and seconds played in the last seven days
+    pub sessions_this_week: usize,
+    pub playtime_this_week: u64,
+}
+
+const WEEK_SECS: u64 = 7 * 24 * 60 * 60;
+
+#[derive(Debug, Serialize, Deserialize, Default)]
+struct LibraryData {
+    version: u32,
+    /// Games found by the last scan
+    #[serde(default)]
+    games: Vec<Game>,
+    /// Games added by hand through `add_game`
+    #[serde(default)]
+    manual_games: Vec<Game>,
+    /// User edits keyed by game ID
+    #[serde(default)]
+    edits: HashMap<String, GameEdit>,
+    /// Per-game launch settings keyed by game ID
+    #[serde(default)]
+    launch_overrides: HashMap<String, LaunchOverrides>,
+    /// Play sessions keyed by game ID, oldest first
+    #[serde(default)]
+    sessions: HashMap<String, Vec<Session>>,
+    /// Favorites set here, keyed by game ID. They win over what the
+    /// emulator says.
+    #[serde(default)]
+    favorites: HashMap<String, bool>,
+}
+
+/// Persistent game library stored as JSON in the app data directory
+#[derive(Debug)]
+pub struct Library {
+    path: PathBuf,
+    data: LibraryData,
+}
+
+impl Library {
+    /// Load the library from `path`, falling back to the backup copy if the
+    /// main file is unreadable. A missing file yields an empty library.
+    pub fn load(path: PathBuf) -> Self {
+        let data = match Self::read_data(&path) {
+            Ok(Some(data)) => data,
+            Ok(None) => LibraryData {
+                version: LIBRARY_VERSION,
+                ..Default::default()
+            },
+            Err(e) => {
+                log::warn!("Failed to load library {}: {}", path.display(), e);
+                store::set_aside_corrupt(&path);
+
+                match Self::read_data(&store::backup_path(&path)) {
+                    Ok(Some(data)) => {
+                        log::info!("Restored library from backup");
+                        data
+                    }
+                    _ => LibraryData {
+                        version: LIBRARY_VERSION,
+                        ..Default::default()
+                    },
+                }
+            }
+        };
+
+        Library { path, data }
+    }
+
+    fn read_data(path: &Path) -> Result<Option<LibraryData>, String> {
+        let Some(value) = store::read_json(path)? else {
+            return Ok(None);
+        };
+        let value = migrate(value)?;
+        serde_json::from_value(value)
+            .map(Some)
+            .map_err(|e| e.to_string())
+    }
+
+    /// Write the library to disk without risking a torn file
+    pub fn save(&self) -> Result<(), String> {
+        store::write_json_atomic(&self.path, &self.data)
+    }
+
+    /// All games with manual additions merged in, titles localized to
+    /// `languages` and user edits applied
+    pub fn games(&self, languages: &[String]) -> Vec<Game> {
+        let mut seen = HashSet::new();
+        let mut games = Vec::new();
+
+        for game in self.data.games.iter().chain(self.data.manual_games.iter()) {
+            if !seen.insert(game.id.clone()) {
+                continue;
+            }
+
+            let mut game = game.clone();
+            game.localize(languages);
+            if let Some(favorite) = self.data.favorites.get(&game.id) {
+                game.favorite = *favorite;
+            }
+            if let Some(edit) = self.data.edits.get(&game.id) {
+                if let Some(title) = &edit.title {
+                    game.title = title.clone();
+                }
+                if let Some(icon) = &edit.icon {
+                    game.icon = Some(icon.clone());
+                }
+            }
+            games.push(game);
+        }
+
+        games
+    }
+
+    /// Replace the scanned games with the result of a fresh scan
+    pub fn set_scanned_games(&mut self, games: Vec<Game>) {
+        self.data.games = games;
+    }
+
+    /// Remember a manually added game
+    pub fn add_manual_game(&mut self, game: Game) {
+        self.data.manual_games.retain(|g| g.id != game.id);
+        self.data.manual_games.push(game);
+    }
+
+    /// The scanned or manually added game with `id`, as stored
+    pub fn game(&self, id: &str) -> Option<&Game> {
+        self.data
+            .games
+            .iter()
+            .chain(self.data.manual_games.iter())
+            .find(|g| g.id == id)
+    }
+
+    fn contains(&self, id: &str) -> bool {
+        self.game(id).is_some()
+    }
+
+    /// Record a user edit for a game, replacing any previous one
+    pub fn edit_game(&mut self, id: &str, edit: GameEdit) -> Result<(), String> {
+        if !self.contains(id) {
+            return Err("Game not found".to_string());
+        }
+
+        if edit == GameEdit::default() {
+            self.data.edits.remove(id);
+        } else {
+            self.data.edits.insert(id.to_string(), edit);
+        }
+        Ok(())
+    }
+
+    /// Launch overrides for a game; the defaults if it has none
+    pub fn launch_overrides(&self, id: &str) -> LaunchOverrides {
+        self.data
+            .launch_overrides
+            .get(id)
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    /// Set a game's launch overrides, replacing any previous ones
+    pub fn set_launch_overrides(
+        &mut self,
+        id: &str,
+        overrides: LaunchOverrides,
+    ) -> Result<(), String> {
+        if !self.contains(id) {
+            return Err("Game not found".to_string());
+        }
+
+        if overrides == LaunchOverrides::default() {
+            self.data.launch_overrides.remove(id);
+        } else {
+            self.data.launch_overrides.insert(id.to_string(), overrides);
+        }
+        Ok(())
+    }
+
+    /// Mark a game as a favorite or not
+    pub fn set_favorite(&mut self, id: &str, favorite: bool) -> Result<(), String> {
+        if !self.contains(id) {
+            return Err("Game not found".to_string());
+        }
+        self.data.favorites.insert(id.to_string(), favorite);
+        Ok(())
+    }
+
+    /// Record a finished play session. Sessions are kept even for games
+    /// that a later scan no longer finds.
+    pub fn record_session(&mut self, game_id: &str, session: Session) {
+        self.data
+            .sessions
+            .entry(game_id.to_string())
+            .or_default()
+            .push(session);
+    }
+
+    /// A game's sessions, oldest first
+    pub fn sessions(&self, game_id: &str) -> Vec<Session> {
+        self.data.sessions.get(game_id).cloned().unwrap_or_default()
+    }
+
+    /// Playtime totals for every game that was played here or in its
+    /// emulator, most recently played first. `now` is in seconds since the
+    /// Unix epoch.
+    pub fn play_stats(&self, now: u64) -> Vec<PlayStats> {
+        let week_start = now.saturating_sub(WEEK_SECS);
+        let mut stats: HashMap<&str, PlayStats> = HashMap::new();
+
+        for (game_id, sessions) in &self.data.sessions {
+            if sessions.is_empty() {
+                continue;
+            }
+            let this_week: Vec<&Session> = sessions
+                .iter()
+                .filter(|s| s.ended_at >= week_start)
+                .collect();
+            stats.insert(
+                game_id,
+                PlayStats {
+                    game_id: game_id.clone(),
+                    total_playtime: sessions.iter().map(|s| s.duration).sum(),
+                    last_played: sessions.iter().map(|s| s.ended_at).max().unwrap_or(0),
+                    sessions: sessions.len(),
+                    sessions_this_week: this_week.len(),
+                    playtime_this_week: this_week.iter().map(|s| s.duration).sum(),
+                },
+            );
+        }
+
+        for game in self.data.games.iter().chain(self.data.manual_games.iter()) {
+            let Some(playtime) = game.emulator_playtime else {
+                continue;
+            };
+            let entry = stats.entry(&game.id).or_insert_with(|| PlayStats {
+                game_id: game.id.clone(),
+                total_playtime: 0,
+                last_played: 0,
+                sessions: 0,
+                sessions_this_week: 0,
+                playtime_this_week: 0,
+            });
+            entry.total_playtime = entry.total_playtime.max(playtime.seconds);
+            entry.last_played = entry.last_played.max(playtime.last_played.unwrap_or(0));
+        }
+
+        let mut stats: Vec<PlayStats> = stats.into_values().collect();
+        stats.sort_by(|a, b| {
+            b.last_played
+                .cmp(&a.last_played)
+                .then_with(|| a.game_id.cmp(&b.game_id))
+        });
+        stats
+    }
+}
+
+/// Upgrade a raw library document to `LIBRARY_VERSION`, one step at a time
+fn migrate(mut value: Value) -> Result<Value, String> {
+    loop {
+        let version = match &value {
+            // Version 0: a bare list of games without any metadata
+            Value::Array(_) => 0,
+            Value::Object(map) => {
+                map.get("version")
+                    .and_then(Value::as_u64)
+                    .ok_or_else(|| "Library has no version".to_string())? as u32
+            }
+            _ => return Err("Library is not a JSON object".to_string()),
+        };
+
+        if version == LIBRARY_VERSION {
+            return Ok(value);
+        }
+        if version > LIBRARY_VERSION {
+            return Err(format!(
+                "Library version {} is newer than supported version {}",
+                version, LIBRARY_VERSION
+            ));
+        }
+
+        value = match version {
+            0 => serde_json::json!({
+                "version": 1,
+                "games": value,
+            }),
+            _ => unreachable!(),
+        };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::emulator::Playtime;
+    use std::fs;
+
+    fn game(id: &str, title: &str) -> Game {
+        Game {
+            id: id.to_string(),
+            title: title.to_string(),
+            path: format!("/games/{}.nsp", title),
+            icon: None,
+            emulator: "yuzu".to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_save_and_load_roundtrip() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(LIBRARY_FILE);
+
+        let mut library = Library::load(path.clone());
+        library.set_scanned_games(vec![game("a", "Scanned")]);
+        library.add_manual_game(game("b", "Manual"));
+        library
+            .edit_game(
+                "a",
+                GameEdit {
+                    title: Some("Renamed".to_string()),
+                    icon: None,
+                },
+            )
+            .unwrap();
+        library.save().unwrap();
+
+        let loaded = Library::load(path);
+        let titles: Vec<_> = loaded.games(&[]).into_iter().map(|g| g.title).collect();
+        assert_eq!(titles, vec!["Renamed", "Manual"]);
+        assert_eq!(loaded.game("b").unwrap().title, "Manual");
+        assert!(loaded.game("missing").is_none());
+    }
+
+    #[test]
+    fn test_launch_overrides() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(LIBRARY_FILE);
+        let overrides = LaunchOverrides {
+            emulator: Some("ryujinx".to_string()),
+            args: vec!["--docked".to_string()],
+            ..Default::default()
+        };
+
+        let mut library = Library::load(path.clone());
+        library.set_scanned_games(vec![game("a", "Scanned")]);
+        assert!(library
+            .set_launch_overrides("missing", overrides.clone())
+            .is_err());
+        library
+            .set_launch_overrides("a", overrides.clone())
+            .unwrap();
+        library.save().unwrap();
+
+        let mut loaded = Library::load(path);
+        assert_eq!(loaded.launch_overrides("a"), overrides);
+        loaded
+            .set_launch_overrides("a", LaunchOverrides::default())
+            .unwrap();
+        assert!(loaded.data.launch_overrides.is_empty());
+    }
+
+    #[test]
+    fn test_play_stats() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(LIBRARY_FILE);
+        let session = |ended_at: u64, duration: u64| Session {
+            started_at: ended_at - duration,
+            ended_at,
+            duration,
+            emulator: "yuzu".to_string(),
+            code: Some(0),
+            signal: None,
+            crashed: false,
+            log: None,
+        };
+        let now = 100 * WEEK_SECS;
+
+        let mut library = Library::load(path.clone());
+        library.record_session("a", session(now - 2 * WEEK_SECS, 600));
+        library.record_session("a", session(now - 3600, 1200));
+        library.record_session("b", session(now - 60, 300));
+        library.save().unwrap();
+
+        let mut library = Library::load(path);
+        assert_eq!(library.sessions("a").len(), 2);
+        // Imported playtime counts when it is higher than our own
+        library.set_scanned_games(vec![
+            Game {
+                emulator_playtime: Some(Playtime {
+                    seconds: 900,
+                    last_played: Some(now - 30),
+                }),
+                ..game("b", "Imported")
+            },
+            Game {
+                emulator_playtime: Some(Playtime {
+                    seconds: 60,
+                    last_played: None,
+                }),
+                ..game("c", "Never here")
+            },
+        ]);
+
+        let stats = library.play_stats(now);
+        assert_eq!(stats.len(), 3);
+        assert_eq!(stats[0].game_id, "b");
+        assert_eq!(stats[0].total_playtime, 900);
+        assert_eq!(stats[0].last_played, now - 30);
+        assert_eq!(stats[0].sessions, 1);
+        assert_eq!(stats[2].game_id, "c");
+        assert_eq!(
+            stats[1],
+            PlayStats {
+                game_id: "a".to_string(),
+                total_playtime: 1800,
+                last_played: now - 3600,
+                sessions: 2,
+                sessions_this_week: 1,
+                playtime_this_week: 1200,
+            }
+        );
+    }
+
+    #[test]
+    fn test_favorites_override_scan() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut library = Library::load(dir.path().join(LIBRARY_FILE));
+        library.set_scanned_games(vec![Game {
+            favorite: true,
+            ..game("a", "Favorite in emulator")
+        }]);
+        assert!(library.games(&[])[0].favorite);
+
+        library.set_favorite("a", false).unwrap();
+        assert!(!library.games(&[])[0].favorite);
+        assert!(library.set_favorite("missing", true).is_err());
+    }
+
+    #[test]
+    fn test_migrates_unversioned_library() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(LIBRARY_FILE);
+        fs::write(
+            &path,
+            serde_json::to_string(&vec![game("a", "Legacy")]).unwrap(),
+        )
+        .unwrap();
+
+        let library = Library::load(path);
+        assert_eq!(library.data.version, LIBRARY_VERSION);
+        assert_eq!(library.games(&[])[0].title, "Legacy");
+    }
+
+    #[test]
+    fn test_corrupt_library_falls_back_to_backup() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(LIBRARY_FILE);
+
+        let mut library = Library::load(path.clone());
+        library.add_manual_game(game("a", "First"));
+        library.save().unwrap();
+        library.add_manual_game(game("b", "Second"));
+        library.save().unwrap();
+
+        // Simulate a torn write of the main file
+        fs::write(&path, "{\"version\": 1, \"ga").unwrap();
+
+        let restored = Library::load(path.clone());
+        assert_eq!(restored.games(&[]).len(), 1);
+        assert!(path.with_extension("json.corrupt").exists());
+    }
+}
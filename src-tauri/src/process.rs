@@ -0,0 +1,449 @@
+//! The emulator process of the game the launcher started
+
+use serde::Serialize;
+use std::io;
+use std::process::{Child, Command, ExitStatus};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use crate::games::Game;
+use crate::session_log::{self, SessionLog};
+
+/// How often the watcher checks whether the game is still running
+pub const POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+/// How long `stop_game` waits for the emulator to quit before killing it
+pub const STOP_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Lifecycle of the running game
+#[derive(Debug, Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum RunState {
+    Running,
+    /// Frozen by `suspend`, using no CPU until resumed
+    Suspended,
+    /// Asked to quit and waiting for it to exit
+    Stopping,
+}
+
+/// A launched game and its emulator process
+#[derive(Debug)]
+pub struct RunningGame {
+    game_id: String,
+    title: String,
+    emulator: String,
+    child: Child,
+    state: RunState,
+    /// Seconds since the Unix epoch, for the UI
+    started_at: u64,
+    started: Instant,
+    /// When the current suspension began
+    suspended_since: Option<Instant>,
+    /// Time spent suspended before the current suspension
+    suspended_for: Duration,
+    /// File name of the session log
+    log: Option<String>,
+}
+
+/// What the UI gets to know about the running game
+#[derive(Debug, Serialize, Clone, PartialEq)]
+pub struct RunningGameInfo {
+    pub game_id: String,
+    pub title: String,
+    pub emulator: String,
+    pub pid: u32,
+    pub started_at: u64,
+    pub state: RunState,
+    /// File name of the session log, for `get_session_log`
+    pub log: Option<String>,
+}
+
+/// Payload of the `game-exited` and `game-crashed` events
+#[derive(Debug, Serialize, Clone, PartialEq)]
+pub struct GameExit {
+    pub game_id: String,
+    pub title: String,
+    /// Emulator profile the game ran with
+    pub emulator: String,
+    /// Seconds since the Unix epoch
+    pub started_at: u64,
+    pub ended_at: u64,
+    /// Exit code, if the process exited on its own
+    pub code: Option<i32>,
+    /// Signal that terminated the process (Unix only)
+    pub signal: Option<i32>,
+    /// Seconds played, not counting time suspended
+    pub duration: u64,
+    /// File name of the session log
+    pub log: Option<String>,
+    /// Exited after the user stopped it
+    pub stopped: bool,
+    /// Exited with an error code or was killed by a signal, without
+    /// being stopped
+    pub crashed: bool,
+}
+
+/// Start `cmd` in a process group of its own so the emulator and anything
+/// it starts can be signalled together
+pub fn spawn(mut cmd: Command) -> io::Result<Child> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::CommandExt;
+        cmd.process_group(0);
+    }
+    cmd.spawn()
+}
+
+/// Seconds since the Unix epoch
+pub fn unix_time() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+impl RunningGame {
+    /// Track `child`, which runs `game` with the profile `emulator`
+    pub fn new(game: &Game, emulator: &str, child: Child) -> Self {
+        RunningGame {
+            game_id: game.id.clone(),
+            title: game.title.clone(),
+            emulator: emulator.to_string(),
+            child,
+            state: RunState::Running,
+            started_at: unix_time(),
+            started: Instant::now(),
+            suspended_since: None,
+            suspended_for: Duration::ZERO,
+            log: None,
+        }
+    }
+
+    /// When the game started, in seconds since the Unix epoch
+    pub fn started_at(&self) -> u64 {
+        self.started_at
+    }
+
+    /// Collect the emulator's output in `log`, or throw it away
+    pub fn capture_output(&mut self, log: Option<&SessionLog>) {
+        match log {
+            Some(log) => {
+                log.capture(&mut self.child);
+                self.log = Some(log.name());
+            }
+            None => session_log::discard_output(&mut self.child),
+        }
+    }
+
+    pub fn pid(&self) -> u32 {
+        self.child.id()
+    }
+
+    pub fn info(&self) -> RunningGameInfo {
+        RunningGameInfo {
+            game_id: self.game_id.clone(),
+            title: self.title.clone(),
+            emulator: self.emulator.clone(),
+            pid: self.pid(),
+            started_at: self.started_at,
+            state: self.state,
+            log: self.log.clone(),
+        }
+    }
+
+    /// Ask the emulator to quit: SIGTERM to its process group on Unix, a
+    /// close request to its process tree on Windows
+    pub fn terminate(&mut self) -> Result<(), String> {
+        let suspended = self.state == RunState::Suspended;
+        self.state = RunState::Stopping;
+        signal_tree(self.pid(), false)
+            .and_then(|()| {
+                // A stopped process only acts on SIGTERM once continued
+                if suspended {
+                    set_suspended(self.pid(), false)
+                } else {
+                    Ok(())
+                }
+            })
+            .map_err(|e| format!("Failed to stop {}: {}", self.title, e))
+    }
+
+    /// Kill the emulator and every process it started
+    pub fn kill(&mut self) -> Result<(), String> {
+        self.state = RunState::Stopping;
+        signal_tree(self.pid(), true).map_err(|e| format!("Failed to kill {}: {}", self.title, e))
+    }
+
+    /// Freeze the emulator and its children, like the Switch's sleep mode
+    pub fn suspend(&mut self) -> Result<(), String> {
+        match self.state {
+            RunState::Running => {}
+            RunState::Suspended => return Ok(()),
+            RunState::Stopping => return Err("Game is stopping".to_string()),
+        }
+        set_suspended(self.pid(), true)
+            .map_err(|e| format!("Failed to suspend {}: {}", self.title, e))?;
+        self.state = RunState::Suspended;
+        self.suspended_since = Some(Instant::now());
+        Ok(())
+    }
+
+    /// Continue a suspended emulator
+    pub fn resume(&mut self) -> Result<(), String> {
+        match self.state {
+            RunState::Suspended => {}
+            RunState::Running => return Ok(()),
+            RunState::Stopping => return Err("Game is stopping".to_string()),
+        }
+        set_suspended(self.pid(), false)
+            .map_err(|e| format!("Failed to resume {}: {}", self.title, e))?;
+        self.state = RunState::Running;
+        if let Some(since) = self.suspended_since.take() {
+            self.suspended_for += since.elapsed();
+        }
+        Ok(())
+    }
+
+    /// Check on the process without blocking. Returns how it ended once
+    /// it has exited, and `None` while it is still running.
+    pub fn poll(&mut self) -> Result<Option<GameExit>, String> {
+        let status = self
+            .child
+            .try_wait()
+            .map_err(|e| format!("Failed to check running game: {}", e))?;
+        Ok(status.map(|status| self.exit(status)))
+    }
+
+    fn exit(&self, status: ExitStatus) -> GameExit {
+        let signal = exit_signal(&status);
+        let stopped = self.state == RunState::Stopping;
+        let suspended =
+            self.suspended_for + self.suspended_since.map_or(Duration::ZERO, |s| s.elapsed());
+        GameExit {
+            game_id: self.game_id.clone(),
+            title: self.title.clone(),
+            emulator: self.emulator.clone(),
+            started_at: self.started_at,
+            ended_at: unix_time(),
+            code: status.code(),
+            signal,
+            duration: self.started.elapsed().saturating_sub(suspended).as_secs(),
+            log: self.log.clone(),
+            stopped,
+            crashed: !stopped && !status.success(),
+        }
+    }
+}
+
+#[cfg(unix)]
+fn signal_tree(pid: u32, force: bool) -> io::Result<()> {
+    signal_group(pid, if force { libc::SIGKILL } else { libc::SIGTERM })
+}
+
+#[cfg(unix)]
+fn set_suspended(pid: u32, suspended: bool) -> io::Result<()> {
+    signal_group(
+        pid,
+        if suspended {
+            libc::SIGSTOP
+        } else {
+            libc::SIGCONT
+        },
+    )
+}
+
+#[cfg(not(unix))]
+fn set_suspended(_pid: u32, _suspended: bool) -> io::Result<()> {
+    Err(io::Error::new(
+        io::ErrorKind::Unsupported,
+        "not supported on this platform",
+    ))
+}
+
+/// Send `signal` to the process group led by `pid`
+#[cfg(unix)]
+fn signal_group(pid: u32, signal: libc::c_int) -> io::Result<()> {
+    // The emulator leads its own process group, see `spawn`
+    if unsafe { libc::kill(-(pid as libc::pid_t), signal) } == 0 {
+        return Ok(());
+    }
+    match io::Error::last_os_error() {
+        // Already gone
+        e if e.raw_os_error() == Some(libc::ESRCH) => Ok(()),
+        e => Err(e),
+    }
+}
+
+#[cfg(windows)]
+fn signal_tree(pid: u32, force: bool) -> io::Result<()> {
+    let mut cmd = Command::new("taskkill");
+    cmd.args(["/PID", &pid.to_string(), "/T"]);
+    if force {
+        cmd.arg("/F");
+    }
+    let output = cmd.output()?;
+    if output.status.success() {
+        Ok(())
+    } else {
+        Err(io::Error::other(
+            String::from_utf8_lossy(&output.stderr).trim().to_string(),
+        ))
+    }
+}
+
+#[cfg(unix)]
+fn exit_signal(status: &ExitStatus) -> Option<i32> {
+    use std::os::unix::process::ExitStatusExt;
+    status.signal()
+}
+
+#[cfg(not(unix))]
+fn exit_signal(_status: &ExitStatus) -> Option<i32> {
+    None
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+
+    fn run(script: &str) -> RunningGame {
+        let game = Game {
+            id: "game".to_string(),
+            title: "Game".to_string(),
+            emulator: "yuzu".to_string(),
+            ..Default::default()
+        };
+        let mut cmd = Command::new("sh");
+        cmd.arg("-c").arg(script);
+        let child = spawn(cmd).unwrap();
+        RunningGame::new(&game, "yuzu", child)
+    }
+
+    fn wait_for_exit(running: &mut RunningGame) -> GameExit {
+        loop {
+            if let Some(exit) = running.poll().unwrap() {
+                return exit;
+            }
+            std::thread::sleep(Duration::from_millis(10));
+        }
+    }
+
+    #[test]
+    fn test_clean_exit() {
+        let mut running = run("exit 0");
+        assert_eq!(running.info().game_id, "game");
+        let exit = wait_for_exit(&mut running);
+        assert_eq!(exit.code, Some(0));
+        assert_eq!(exit.signal, None);
+        assert_eq!(exit.emulator, "yuzu");
+        assert!(exit.ended_at >= exit.started_at);
+        assert!(!exit.crashed);
+    }
+
+    #[test]
+    fn test_crash() {
+        let exit = wait_for_exit(&mut run("exit 3"));
+        assert_eq!(exit.code, Some(3));
+        assert!(exit.crashed);
+
+        let exit = wait_for_exit(&mut run("kill -SEGV $$"));
+        assert_eq!(exit.code, None);
+        assert_eq!(exit.signal, Some(11));
+        assert!(exit.crashed);
+    }
+
+    #[test]
+    fn test_still_running() {
+        let mut running = run("sleep 5");
+        assert_eq!(running.poll().unwrap(), None);
+        running.child.kill().unwrap();
+        assert!(wait_for_exit(&mut running).crashed);
+    }
+
+    #[test]
+    fn test_terminate() {
+        let mut running = run("sleep 30");
+        // Time suspended doesn't count as played
+        running.suspended_for = Duration::from_secs(3600);
+        running.terminate().unwrap();
+        assert_eq!(running.info().state, RunState::Stopping);
+
+        let exit = wait_for_exit(&mut running);
+        assert_eq!(exit.signal, Some(libc::SIGTERM));
+        assert_eq!(exit.duration, 0);
+        assert!(exit.stopped);
+        assert!(!exit.crashed);
+    }
+
+    #[cfg(target_os = "linux")]
+    fn process_state(pid: u32) -> Option<char> {
+        let stat = std::fs::read_to_string(format!("/proc/{}/stat", pid)).ok()?;
+        stat.rsplit_once(") ")?.1.chars().next()
+    }
+
+    /// Wait for signals to take effect on `pid`
+    #[cfg(target_os = "linux")]
+    fn wait_for_state(pid: u32, done: impl Fn(Option<char>) -> bool) {
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while !done(process_state(pid)) {
+            assert!(
+                Instant::now() < deadline,
+                "process {} did not change state",
+                pid
+            );
+            std::thread::sleep(Duration::from_millis(10));
+        }
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_suspend_and_resume() {
+        let mut running = run("sleep 30");
+        running.suspend().unwrap();
+        assert_eq!(running.info().state, RunState::Suspended);
+        // Stopped, not exited
+        wait_for_state(running.pid(), |state| state == Some('T'));
+        assert_eq!(running.poll().unwrap(), None);
+
+        running.resume().unwrap();
+        assert_eq!(running.info().state, RunState::Running);
+        wait_for_state(running.pid(), |state| state != Some('T'));
+
+        // Stopping a suspended game still ends it
+        running.suspend().unwrap();
+        running.terminate().unwrap();
+        assert!(running.resume().is_err());
+        let exit = wait_for_exit(&mut running);
+        assert_eq!(exit.signal, Some(libc::SIGTERM));
+        assert!(exit.stopped);
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_kill_process_tree() {
+        let dir = tempfile::tempdir().unwrap();
+        let pid_file = dir.path().join("pid");
+        // Ignores SIGTERM and starts a child of its own
+        let mut running = run(&format!(
+            "trap '' TERM; sleep 30 & echo $! > {}; wait",
+            pid_file.display()
+        ));
+        while std::fs::read_to_string(&pid_file).map_or(true, |s| !s.ends_with('\n')) {
+            std::thread::sleep(Duration::from_millis(10));
+        }
+        let grandchild: u32 = std::fs::read_to_string(&pid_file)
+            .unwrap()
+            .trim()
+            .parse()
+            .unwrap();
+
+        running.terminate().unwrap();
+        std::thread::sleep(Duration::from_millis(100));
+        assert_eq!(running.poll().unwrap(), None);
+
+        running.kill().unwrap();
+        assert_eq!(wait_for_exit(&mut running).signal, Some(libc::SIGKILL));
+
+        // The grandchild is gone too, or at worst a zombie
+        wait_for_state(grandchild, |state| matches!(state, None | Some('Z')));
+    }
+}
@@ -0,0 +1,342 @@
+//! Reader for the INI files Qt's QSettings writes, such as yuzu's
+//! `qt-config.ini`
+
+use std::collections::HashMap;
+
+/// A decoded QSettings value
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    String(String),
+    /// Unquoted comma separated values
+    List(Vec<String>),
+    /// `@ByteArray(...)`
+    ByteArray(Vec<u8>),
+    /// `@Variant(...)`, a serialized QVariant we don't decode
+    Variant(Vec<u8>),
+    /// `@Invalid()`
+    Invalid,
+}
+
+impl Value {
+    /// The value as text; byte arrays are decoded as UTF-8
+    pub fn text(&self) -> Option<String> {
+        match self {
+            Value::String(s) => Some(s.clone()),
+            Value::ByteArray(bytes) => Some(String::from_utf8_lossy(bytes).into_owned()),
+            _ => None,
+        }
+    }
+
+    pub fn as_bool(&self) -> Option<bool> {
+        match self.text()?.to_ascii_lowercase().as_str() {
+            "true" | "1" => Some(true),
+            "false" | "0" => Some(false),
+            _ => None,
+        }
+    }
+}
+
+/// Parsed settings, by section and then key. Keys use `/` as the group
+/// separator (QSettings writes it as `\` in the file). Keys outside any
+/// section, or in `[General]`, live in the `""` section.
+#[derive(Debug, Clone, Default)]
+pub struct QSettings {
+    sections: HashMap<String, HashMap<String, Value>>,
+}
+
+impl QSettings {
+    pub fn parse(contents: &str) -> Self {
+        let mut settings = QSettings::default();
+        let mut section = String::new();
+
+        for line in contents.lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with(';') || line.starts_with('#') {
+                continue;
+            }
+
+            if let Some(name) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+                section = unescape_key(name.trim());
+                if section.eq_ignore_ascii_case("General") {
+                    section.clear();
+                }
+                continue;
+            }
+
+            let Some((key, value)) = line.split_once('=') else {
+                continue;
+            };
+            settings
+                .sections
+                .entry(section.clone())
+                .or_default()
+                .insert(unescape_key(key.trim()), parse_value(value));
+        }
+
+        settings
+    }
+
+    pub fn get(&self, section: &str, key: &str) -> Option<&Value> {
+        self.sections.get(section)?.get(key)
+    }
+
+    /// Read an array written with `QSettings::beginWriteArray`: entries
+    /// `prefix/1/field` .. `prefix/N/field` with the count in `prefix/size`.
+    /// Each entry maps field names to values.
+    pub fn array(&self, section: &str, prefix: &str) -> Vec<HashMap<String, Value>> {
+        let Some(keys) = self.sections.get(section) else {
+            return Vec::new();
+        };
+
+        let mut entries: Vec<HashMap<String, Value>> = Vec::new();
+        let group = format!("{}/", prefix);
+        for (key, value) in keys {
+            let Some((index, field)) = key
+                .strip_prefix(&group)
+                .and_then(|rest| rest.split_once('/'))
+            else {
+                continue;
+            };
+            let Ok(index) = index.parse::<usize>() else {
+                continue;
+            };
+            if index == 0 {
+                continue;
+            }
+            if entries.len() < index {
+                entries.resize_with(index, HashMap::new);
+            }
+            entries[index - 1].insert(field.to_string(), value.clone());
+        }
+
+        // QSettings only reads up to `size`; entries past it are stale
+        if let Some(size) = self
+            .get(section, &format!("{}/size", prefix))
+            .and_then(Value::text)
+            .and_then(|s| s.parse::<usize>().ok())
+        {
+            entries.truncate(size);
+        }
+        entries
+    }
+}
+
+/// Decode a key or section name: `\` separates groups and other special
+/// characters are written as `%XX` or `%UXXXX`
+fn unescape_key(key: &str) -> String {
+    let mut out = String::new();
+    let mut chars = key.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => out.push('/'),
+            '%' => {
+                let digits = if chars.peek() == Some(&'U') {
+                    chars.next();
+                    4
+                } else {
+                    2
+                };
+                let hex: String = chars.by_ref().take(digits).collect();
+                match u32::from_str_radix(&hex, 16).ok().and_then(char::from_u32) {
+                    Some(decoded) => out.push(decoded),
+                    None => {
+                        out.push('%');
+                        out.push_str(&hex);
+                    }
+                }
+            }
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+/// Decode a value, handling quotes, escapes, comma separated lists and
+/// `@`-prefixed special types
+fn parse_value(raw: &str) -> Value {
+    let mut items = unescape_list(raw);
+    if items.len() != 1 {
+        return Value::List(items);
+    }
+
+    let item = items.remove(0);
+    if let Some(rest) = item.strip_prefix("@@") {
+        return Value::String(format!("@{}", rest));
+    }
+    if let Some(data) = item
+        .strip_prefix("@ByteArray(")
+        .and_then(|s| s.strip_suffix(')'))
+    {
+        return Value::ByteArray(latin1(data));
+    }
+    if let Some(data) = item
+        .strip_prefix("@Variant(")
+        .and_then(|s| s.strip_suffix(')'))
+    {
+        return Value::Variant(latin1(data));
+    }
+    if item == "@Invalid()" {
+        return Value::Invalid;
+    }
+    Value::String(item)
+}
+
+/// Byte arrays are written one character per byte
+fn latin1(s: &str) -> Vec<u8> {
+    s.chars().map(|c| c as u32 as u8).collect()
+}
+
+/// Split an INI value on unquoted commas, unescaping each item. Unquoted
+/// whitespace around items is dropped and `;` starts a comment.
+fn unescape_list(raw: &str) -> Vec<String> {
+    let mut items = Vec::new();
+    let mut current = String::new();
+    // Length of `current` up to the last quoted or escaped character, so
+    // trailing whitespace trimming doesn't eat it
+    let mut kept = 0;
+    let mut in_quotes = false;
+    let mut chars = raw.trim_start().chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '"' => {
+                in_quotes = !in_quotes;
+                kept = current.len();
+                continue;
+            }
+            '\\' => {
+                if let Some(escaped) = unescape_char(&mut chars) {
+                    current.push(escaped);
+                }
+                kept = current.len();
+                continue;
+            }
+            ',' if !in_quotes => {
+                items.push(trim_unquoted(&current, kept));
+                current.clear();
+                kept = 0;
+                while chars.next_if(|c| c.is_whitespace()).is_some() {}
+                continue;
+            }
+            ';' if !in_quotes => break,
+            _ => current.push(c),
+        }
+        if in_quotes {
+            kept = current.len();
+        }
+    }
+
+    items.push(trim_unquoted(&current, kept));
+    items
+}
+
+fn trim_unquoted(s: &str, kept: usize) -> String {
+    let (fixed, rest) = s.split_at(kept);
+    format!("{}{}", fixed, rest.trim_end())
+}
+
+/// Decode the escape sequence after a backslash
+fn unescape_char(chars: &mut std::iter::Peekable<std::str::Chars>) -> Option<char> {
+    let c = chars.next()?;
+    let decoded = match c {
+        'a' => '\x07',
+        'b' => '\x08',
+        'f' => '\x0C',
+        'n' => '\n',
+        'r' => '\r',
+        't' => '\t',
+        'v' => '\x0B',
+        'x' => {
+            let mut value = 0u32;
+            for _ in 0..4 {
+                let Some(digit) = chars.peek().and_then(|d| d.to_digit(16)) else {
+                    break;
+                };
+                value = value * 16 + digit;
+                chars.next();
+            }
+            char::from_u32(value)?
+        }
+        '0'..='7' => {
+            let mut value = c.to_digit(8)?;
+            for _ in 0..2 {
+                let Some(digit) = chars.peek().and_then(|d| d.to_digit(8)) else {
+                    break;
+                };
+                value = value * 8 + digit;
+                chars.next();
+            }
+            char::from_u32(value)?
+        }
+        // \\, \", \', \? and anything unknown stand for themselves
+        other => other,
+    };
+    Some(decoded)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sections_and_arrays() {
+        let settings = QSettings::parse(
+            r#"
+[General]
+version=1
+
+[UI]
+Paths\gamedirs\1\path=SDMC
+Paths\gamedirs\1\deep_scan=false
+Paths\gamedirs\2\path=/home/user/My Games
+Paths\gamedirs\2\deep_scan\default=false
+Paths\gamedirs\2\deep_scan=true
+Paths\gamedirs\3\path=/stale
+Paths\gamedirs\size=2
+"#,
+        );
+
+        assert_eq!(
+            settings.get("", "version"),
+            Some(&Value::String("1".to_string()))
+        );
+        let dirs = settings.array("UI", "Paths/gamedirs");
+        assert_eq!(dirs.len(), 2);
+        assert_eq!(dirs[1]["path"].text().unwrap(), "/home/user/My Games");
+        assert_eq!(dirs[1]["deep_scan"].as_bool(), Some(true));
+        assert_eq!(dirs[0]["deep_scan"].as_bool(), Some(false));
+    }
+
+    #[test]
+    fn test_value_escapes() {
+        let settings = QSettings::parse(
+            r#"
+[Test]
+quoted="  C:\\Games, \"Switch\"  "
+list=a, b ,c
+bytes=@ByteArray(/games/\xe9)
+variant=@Variant(\0\0\0\x7f)
+invalid=@Invalid()
+at=@@home
+comment=value ; trailing comment
+key%20with%U00e9=1
+"#,
+        );
+        let get = |key: &str| settings.get("Test", key).unwrap().clone();
+
+        assert_eq!(
+            get("quoted"),
+            Value::String("  C:\\Games, \"Switch\"  ".to_string())
+        );
+        assert_eq!(
+            get("list"),
+            Value::List(vec!["a".to_string(), "b".to_string(), "c".to_string()])
+        );
+        assert_eq!(get("bytes"), Value::ByteArray(b"/games/\xe9".to_vec()));
+        assert_eq!(get("variant"), Value::Variant(vec![0, 0, 0, 0x7f]));
+        assert_eq!(get("invalid"), Value::Invalid);
+        assert_eq!(get("at"), Value::String("@home".to_string()));
+        assert_eq!(get("comment"), Value::String("value".to_string()));
+        assert_eq!(get("key with\u{e9}"), Value::String("1".to_string()));
+    }
+}
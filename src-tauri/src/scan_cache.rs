@@ -0,0 +1,219 @@
+//! Games found by earlier scans, reused while their files are unchanged
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs::Metadata;
+use std::path::PathBuf;
+use std::time::SystemTime;
+
+use crate::games::Game;
+use crate::store;
+
+/// File name of the scan cache inside the app data directory
+pub const SCAN_CACHE_FILE: &str = "scan-cache.json";
+
+/// Identifies a version of a file on disk. A file whose fingerprint
+/// matches the cached one is assumed to have the same contents.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct Fingerprint {
+    pub size: u64,
+    #[serde(default)]
+    pub modified: Option<SystemTime>,
+    /// Catches a file replaced by another of the same size and time
+    #[serde(default)]
+    pub inode: Option<u64>,
+}
+
+impl Fingerprint {
+    pub fn of(metadata: &Metadata) -> Self {
+        #[cfg(unix)]
+        let inode = {
+            use std::os::unix::fs::MetadataExt;
+            Some(metadata.ino())
+        };
+        #[cfg(not(unix))]
+        let inode = None;
+
+        Fingerprint {
+            size: metadata.len(),
+            modified: metadata.modified().ok(),
+            inode,
+        }
+    }
+}
+
+/// What a game entry was built with, besides the file itself. Entries
+/// built with different options are parsed again.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+pub struct ScanOptions {
+    /// Console keys were loaded, so encrypted NCAs could be read
+    pub keys: bool,
+    pub remote_icons: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+struct CacheEntry {
+    fingerprint: Fingerprint,
+    options: ScanOptions,
+    /// The game as built from the file, before emulator title metadata
+    game: Game,
+}
+
+/// How a scan got its games
+#[derive(Debug, Serialize, Clone, Copy, Default, PartialEq)]
+pub struct ScanStats {
+    /// Unchanged files taken from the cache
+    pub reused: usize,
+    /// New or modified files that were read
+    pub parsed: usize,
+    /// Cached files that are gone
+    pub removed: usize,
+}
+
+/// Persisted game entries by file path
+#[derive(Debug, Default)]
+pub struct ScanCache {
+    path: Option<PathBuf>,
+    entries: HashMap<String, CacheEntry>,
+}
+
+impl ScanCache {
+    /// Read the cache at `path`. A missing or unreadable cache only costs
+    /// a full parse, so errors start an empty one.
+    pub fn load(path: PathBuf) -> Self {
+        let entries = match store::read_json(&path).and_then(|value| {
+            value
+                .map(|value| serde_json::from_value(value).map_err(|e| e.to_string()))
+                .transpose()
+        }) {
+            Ok(entries) => entries.unwrap_or_default(),
+            Err(e) => {
+                log::warn!("Discarding scan cache {}: {}", path.display(), e);
+                HashMap::new()
+            }
+        };
+        ScanCache {
+            path: Some(path),
+            entries,
+        }
+    }
+
+    pub fn save(&self) -> Result<(), String> {
+        match &self.path {
+            Some(path) => store::write_json_atomic(path, &self.entries),
+            None => Ok(()),
+        }
+    }
+
+    /// The cached game for `path` if the file still has `fingerprint` and
+    /// was built with the same `options`
+    pub fn get(
+        &self,
+        path: &str,
+        fingerprint: &Fingerprint,
+        options: ScanOptions,
+    ) -> Option<&Game> {
+        self.entries
+            .get(path)
+            .filter(|e| e.fingerprint == *fingerprint && e.options == options)
+            .map(|e| &e.game)
+    }
+
+    pub fn insert(&mut self, fingerprint: Fingerprint, options: ScanOptions, game: Game) {
+        self.entries.insert(
+            game.path.clone(),
+            CacheEntry {
+                fingerprint,
+                options,
+                game,
+            },
+        );
+    }
+
+    pub fn remove(&mut self, path: &str) {
+        self.entries.remove(path);
+    }
+
+    /// Drop entries for files that `keep` doesn't accept, returning how
+    /// many were dropped
+    pub fn retain(&mut self, keep: impl Fn(&str) -> bool) -> usize {
+        let before = self.entries.len();
+        self.entries.retain(|path, _| keep(path));
+        before - self.entries.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use std::path::Path;
+
+    const OPTIONS: ScanOptions = ScanOptions {
+        keys: false,
+        remote_icons: false,
+    };
+
+    fn game(path: &Path) -> Game {
+        Game {
+            id: "1".to_string(),
+            title: "Game".to_string(),
+            path: path.to_string_lossy().to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_fingerprint_match() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("Game.nsp");
+        fs::write(&file, b"one").unwrap();
+        let path = file.to_string_lossy().to_string();
+
+        let mut cache = ScanCache::default();
+        let fingerprint = Fingerprint::of(&fs::metadata(&file).unwrap());
+        cache.insert(fingerprint.clone(), OPTIONS, game(&file));
+
+        assert!(cache.get(&path, &fingerprint, OPTIONS).is_some());
+        let with_keys = ScanOptions {
+            keys: true,
+            ..OPTIONS
+        };
+        assert!(cache.get(&path, &fingerprint, with_keys).is_none());
+
+        fs::write(&file, b"longer").unwrap();
+        let changed = Fingerprint::of(&fs::metadata(&file).unwrap());
+        assert!(cache.get(&path, &changed, OPTIONS).is_none());
+    }
+
+    #[test]
+    fn test_load_save_retain() {
+        let dir = tempfile::tempdir().unwrap();
+        let cache_path = dir.path().join(SCAN_CACHE_FILE);
+        let fingerprint = Fingerprint {
+            size: 1,
+            modified: Some(SystemTime::UNIX_EPOCH),
+            inode: Some(2),
+        };
+
+        let mut cache = ScanCache::load(cache_path.clone());
+        cache.insert(fingerprint.clone(), OPTIONS, game(Path::new("/a/one.nsp")));
+        cache.insert(fingerprint.clone(), OPTIONS, game(Path::new("/a/two.nsp")));
+        cache.insert(
+            fingerprint.clone(),
+            OPTIONS,
+            game(Path::new("/b/three.nsp")),
+        );
+        cache.save().unwrap();
+
+        let mut cache = ScanCache::load(cache_path.clone());
+        assert_eq!(cache.entries.len(), 3);
+        assert!(cache.get("/a/one.nsp", &fingerprint, OPTIONS).is_some());
+
+        assert_eq!(cache.retain(|p| p.starts_with("/a/")), 1);
+        assert_eq!(cache.entries.len(), 2);
+
+        fs::write(&cache_path, "not json").unwrap();
+        assert!(ScanCache::load(cache_path).entries.is_empty());
+    }
+}
@@ -0,0 +1,141 @@
+//! User settings for where and how scans look for games
+
+use globset::{Glob, GlobSet, GlobSetBuilder};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+use crate::emulator::DEFAULT_SCAN_DEPTH;
+
+/// A folder the user added to the scan
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct ScanFolder {
+    pub path: PathBuf,
+    /// Emulator the games found here are started with
+    pub emulator: String,
+    /// WalkDir depth; 1 searches only the folder itself
+    #[serde(default = "default_depth")]
+    pub max_depth: usize,
+}
+
+fn default_depth() -> usize {
+    DEFAULT_SCAN_DEPTH
+}
+
+fn default_true() -> bool {
+    true
+}
+
+/// Where and how scans look for games
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct ScanConfig {
+    /// Scanned before the emulators' folders, so their games belong to
+    /// the emulator picked here
+    #[serde(default)]
+    pub folders: Vec<ScanFolder>,
+    /// Glob patterns for files and folders to skip, matched against the
+    /// full path, e.g. `**/Updates` or `*.nro`
+    #[serde(default)]
+    pub excludes: Vec<String>,
+    #[serde(default)]
+    pub follow_symlinks: bool,
+    /// Also look in common game folders like `~/Games/Switch` and
+    /// `~/Downloads`, and search Downloads for portable emulator installs
+    #[serde(default = "default_true")]
+    pub builtin_locations: bool,
+}
+
+impl Default for ScanConfig {
+    fn default() -> Self {
+        ScanConfig {
+            folders: Vec::new(),
+            excludes: Vec::new(),
+            follow_symlinks: false,
+            builtin_locations: true,
+        }
+    }
+}
+
+impl ScanConfig {
+    /// Check the config before saving it. Emulator IDs are checked by the
+    /// caller, which knows the user's profiles.
+    pub fn validate(&self) -> Result<(), String> {
+        for folder in &self.folders {
+            if !folder.path.is_absolute() {
+                return Err(format!(
+                    "Scan folder must be an absolute path: {}",
+                    folder.path.display()
+                ));
+            }
+            if folder.max_depth == 0 {
+                return Err(format!(
+                    "Scan depth of {} must be at least 1",
+                    folder.path.display()
+                ));
+            }
+        }
+        self.exclude_set().map(|_| ())
+    }
+
+    /// The exclude patterns as one matcher
+    pub fn exclude_set(&self) -> Result<GlobSet, String> {
+        let mut builder = GlobSetBuilder::new();
+        for pattern in &self.excludes {
+            let glob = Glob::new(pattern)
+                .map_err(|e| format!("Invalid exclude pattern {:?}: {}", pattern, e))?;
+            builder.add(glob);
+        }
+        builder.build().map_err(|e| e.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::Path;
+
+    #[test]
+    fn test_exclude_set() {
+        let config = ScanConfig {
+            excludes: vec!["**/Updates".to_string(), "*.nro".to_string()],
+            ..Default::default()
+        };
+        let excludes = config.exclude_set().unwrap();
+        assert!(excludes.is_match(Path::new("/games/Zelda/Updates")));
+        assert!(excludes.is_match(Path::new("/games/homebrew/app.nro")));
+        assert!(!excludes.is_match(Path::new("/games/Zelda/Zelda.nsp")));
+
+        let bad = ScanConfig {
+            excludes: vec!["[unclosed".to_string()],
+            ..Default::default()
+        };
+        assert!(bad.validate().is_err());
+    }
+
+    #[test]
+    fn test_validate_folders() {
+        let mut config = ScanConfig::default();
+        config.folders.push(ScanFolder {
+            path: PathBuf::from("relative/games"),
+            emulator: "yuzu".to_string(),
+            max_depth: 3,
+        });
+        assert!(config.validate().is_err());
+
+        let root = if cfg!(windows) { "C:/games" } else { "/games" };
+        config.folders[0].path = PathBuf::from(root);
+        assert!(config.validate().is_ok());
+        config.folders[0].max_depth = 0;
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn test_defaults() {
+        let config: ScanConfig = serde_json::from_str("{}").unwrap();
+        assert_eq!(config, ScanConfig::default());
+        assert!(config.builtin_locations);
+
+        let folder: ScanFolder =
+            serde_json::from_str(r#"{"path": "/games", "emulator": "yuzu"}"#).unwrap();
+        assert_eq!(folder.max_depth, DEFAULT_SCAN_DEPTH);
+    }
+}
@@ -0,0 +1,362 @@
+//! Emulator output captured into a log file per play session
+
+use std::fs::{self, File};
+use std::io::{self, BufRead, BufReader, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::process::Child;
+use std::sync::{Arc, Mutex};
+
+/// Folder in the app data directory that holds session logs
+pub const LOG_DIR: &str = "logs";
+
+/// A log is rotated to `<name>.1` once it grows past this
+pub const MAX_LOG_SIZE: u64 = 8 * 1024 * 1024;
+
+/// Session logs kept; older ones are deleted when a game starts
+pub const KEEP_LOGS: usize = 20;
+
+/// A session log being written. Clones write to the same file.
+#[derive(Debug, Clone)]
+pub struct SessionLog {
+    path: PathBuf,
+    writer: Arc<Mutex<LogWriter>>,
+}
+
+#[derive(Debug)]
+struct LogWriter {
+    file: File,
+    written: u64,
+    max_size: u64,
+}
+
+impl SessionLog {
+    /// Create the log for a session of `game_id` started at `started_at`
+    /// (seconds since the Unix epoch) in `dir`
+    pub fn create(dir: &Path, game_id: &str, started_at: u64) -> io::Result<Self> {
+        fs::create_dir_all(dir)?;
+        let game_id: String = game_id
+            .chars()
+            .filter(char::is_ascii_alphanumeric)
+            .collect();
+        let path = dir.join(format!("{}-{}.log", game_id, started_at));
+        Self::with_max_size(path, MAX_LOG_SIZE)
+    }
+
+    fn with_max_size(path: PathBuf, max_size: u64) -> io::Result<Self> {
+        let file = File::create(&path)?;
+        Ok(SessionLog {
+            path,
+            writer: Arc::new(Mutex::new(LogWriter {
+                file,
+                written: 0,
+                max_size,
+            })),
+        })
+    }
+
+    /// File name of the log, as stored in the session record
+    pub fn name(&self) -> String {
+        self.path
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default()
+    }
+
+    /// Copy the child's piped stdout and stderr into the log, line by
+    /// line, on background threads that end when the pipes close
+    pub fn capture(&self, child: &mut Child) {
+        if let Some(stdout) = child.stdout.take() {
+            self.copy_lines(stdout, "");
+        }
+        if let Some(stderr) = child.stderr.take() {
+            self.copy_lines(stderr, "[stderr] ");
+        }
+    }
+
+    fn copy_lines(&self, pipe: impl Read + Send + 'static, prefix: &'static str) {
+        let log = self.clone();
+        std::thread::spawn(move || {
+            let mut reader = BufReader::new(pipe);
+            let mut line = Vec::new();
+            loop {
+                line.clear();
+                match reader.read_until(b'\n', &mut line) {
+                    Ok(0) | Err(_) => break,
+                    Ok(_) => {
+                        if !line.ends_with(b"\n") {
+                            line.push(b'\n');
+                        }
+                        if let Err(e) = log.write(prefix.as_bytes(), &line) {
+                            // Keep reading so the emulator doesn't block on
+                            // a full pipe
+                            log::warn!("Failed to write {}: {}", log.path.display(), e);
+                            let _ = io::copy(&mut reader, &mut io::sink());
+                            break;
+                        }
+                    }
+                }
+            }
+        });
+    }
+
+    fn write(&self, prefix: &[u8], line: &[u8]) -> io::Result<()> {
+        let mut writer = self
+            .writer
+            .lock()
+            .map_err(|_| io::Error::other("log writer poisoned"))?;
+        if writer.written >= writer.max_size {
+            // Copy the output so far to `.1`, replacing an older
+            // rotation, and start the file over. Renaming would fail on
+            // Windows while the file is open.
+            writer.file.flush()?;
+            fs::copy(&self.path, rotated_path(&self.path))?;
+            writer.file.set_len(0)?;
+            writer.file.seek(SeekFrom::Start(0))?;
+            writer.written = 0;
+        }
+        writer.file.write_all(prefix)?;
+        writer.file.write_all(line)?;
+        writer.written += (prefix.len() + line.len()) as u64;
+        Ok(())
+    }
+}
+
+/// Drain the child's piped output when there is no log to write it to,
+/// so the emulator doesn't block on a full pipe
+pub fn discard_output(child: &mut Child) {
+    fn drain(mut pipe: impl Read + Send + 'static) {
+        std::thread::spawn(move || io::copy(&mut pipe, &mut io::sink()));
+    }
+    if let Some(stdout) = child.stdout.take() {
+        drain(stdout);
+    }
+    if let Some(stderr) = child.stderr.take() {
+        drain(stderr);
+    }
+}
+
+/// Where a log goes once it is rotated
+fn rotated_path(path: &Path) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(".1");
+    PathBuf::from(name)
+}
+
+/// The path of the log called `name` in `dir`. Only plain file names
+/// are accepted so the UI cannot read files elsewhere.
+pub fn log_path(dir: &Path, name: &str) -> Result<PathBuf, String> {
+    let is_plain = Path::new(name).file_name() == Some(name.as_ref())
+        && name.ends_with(".log")
+        && !name.starts_with('.');
+    if !is_plain {
+        return Err(format!("Invalid log name: {}", name));
+    }
+    Ok(dir.join(name))
+}
+
+/// The last `lines` lines of a log, or all of it. Reads only the end of
+/// large files.
+pub fn tail(path: &Path, lines: Option<usize>) -> io::Result<String> {
+    let mut file = File::open(path)?;
+    let Some(lines) = lines else {
+        let mut contents = Vec::new();
+        file.read_to_end(&mut contents)?;
+        return Ok(String::from_utf8_lossy(&contents).into_owned());
+    };
+
+    // Read backwards in blocks until enough newlines are found
+    const BLOCK: u64 = 64 * 1024;
+    let len = file.metadata()?.len();
+    let mut start = len;
+    let mut buf = Vec::new();
+    while start > 0 {
+        let next = start.saturating_sub(BLOCK);
+        let mut block = vec![0; (start - next) as usize];
+        file.seek(SeekFrom::Start(next))?;
+        file.read_exact(&mut block)?;
+        block.extend_from_slice(&buf);
+        buf = block;
+        start = next;
+
+        // A trailing newline ends the last line rather than starting one
+        let body = buf.strip_suffix(b"\n").unwrap_or(&buf);
+        if body.iter().filter(|b| **b == b'\n').count() >= lines {
+            break;
+        }
+    }
+
+    // Start after the newline that ends the line before the last `lines`
+    let body = buf.strip_suffix(b"\n").unwrap_or(&buf);
+    let skip = match lines.checked_sub(1) {
+        None => buf.len(),
+        Some(n) => body
+            .iter()
+            .enumerate()
+            .filter(|(_, b)| **b == b'\n')
+            .map(|(i, _)| i)
+            .rev()
+            .nth(n)
+            .map_or(0, |i| i + 1),
+    };
+    Ok(String::from_utf8_lossy(&buf[skip..]).into_owned())
+}
+
+/// Delete all but the newest `keep` session logs in `dir`
+pub fn prune(dir: &Path, keep: usize) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+    let mut logs: Vec<(std::time::SystemTime, PathBuf)> = entries
+        .filter_map(|e| e.ok())
+        .map(|e| e.path())
+        .filter(|path| path.extension() == Some("log".as_ref()))
+        .filter_map(|path| Some((fs::metadata(&path).ok()?.modified().ok()?, path)))
+        .collect();
+    logs.sort_by_key(|(modified, _)| std::cmp::Reverse(*modified));
+
+    for (_, path) in logs.into_iter().skip(keep) {
+        let _ = fs::remove_file(rotated_path(&path));
+        if let Err(e) = fs::remove_file(&path) {
+            log::warn!("Failed to delete old log {}: {}", path.display(), e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tail() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("game-1.log");
+        fs::write(&path, "one\ntwo\nthree\n").unwrap();
+
+        assert_eq!(tail(&path, None).unwrap(), "one\ntwo\nthree\n");
+        assert_eq!(tail(&path, Some(2)).unwrap(), "two\nthree\n");
+        assert_eq!(tail(&path, Some(5)).unwrap(), "one\ntwo\nthree\n");
+        assert_eq!(tail(&path, Some(0)).unwrap(), "");
+
+        // Longer than one read block
+        let long: String = (0..20000).map(|i| format!("line {}\n", i)).collect();
+        fs::write(&path, long).unwrap();
+        assert_eq!(tail(&path, Some(2)).unwrap(), "line 19998\nline 19999\n");
+    }
+
+    #[test]
+    fn test_rotation() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("game-1.log");
+        let log = SessionLog::with_max_size(path.clone(), 10).unwrap();
+        log.write(b"", b"first line\n").unwrap();
+        log.write(b"[stderr] ", b"second\n").unwrap();
+
+        assert_eq!(
+            fs::read_to_string(rotated_path(&path)).unwrap(),
+            "first line\n"
+        );
+        assert_eq!(fs::read_to_string(&path).unwrap(), "[stderr] second\n");
+    }
+
+    #[test]
+    fn test_log_path() {
+        let dir = Path::new("/data/logs");
+        assert_eq!(log_path(dir, "abc-1.log").unwrap(), dir.join("abc-1.log"));
+        assert!(log_path(dir, "../settings.json").is_err());
+        assert!(log_path(dir, "../x.log").is_err());
+        assert!(log_path(dir, "/etc/x.log").is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_capture() {
+        use std::process::{Command, Stdio};
+
+        let dir = tempfile::tempdir().unwrap();
+        let log = SessionLog::create(dir.path(), "ab/c", 7).unwrap();
+        assert_eq!(log.name(), "abc-7.log");
+
+        let mut child = Command::new("sh")
+            .arg("-c")
+            .arg("echo out; echo err >&2")
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .unwrap();
+        log.capture(&mut child);
+        child.wait().unwrap();
+
+        let path = dir.path().join("abc-7.log");
+        let deadline = std::time::Instant::now() + std::time::Duration::from_secs(5);
+        loop {
+            let contents = fs::read_to_string(&path).unwrap();
+            let mut lines: Vec<_> = contents.lines().collect();
+            lines.sort();
+            if lines == ["[stderr] err", "out"] {
+                break;
+            }
+            assert!(std::time::Instant::now() < deadline, "got {:?}", contents);
+            std::thread::sleep(std::time::Duration::from_millis(10));
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_capture_drains_after_write_error() {
+        use std::process::{Command, Stdio};
+
+        let dir = tempfile::tempdir().unwrap();
+        let log = SessionLog::with_max_size(dir.path().join("game-1.log"), 0).unwrap();
+        // Rotation can't copy a file that is gone, so every write fails
+        fs::remove_file(dir.path().join("game-1.log")).unwrap();
+
+        // Far more than a pipe holds. If the output stops being read the
+        // child blocks, or dies of SIGPIPE once the pipe is closed.
+        let mut child = Command::new("sh")
+            .arg("-c")
+            .arg("yes | head -n 200000")
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .unwrap();
+        log.capture(&mut child);
+
+        let deadline = std::time::Instant::now() + std::time::Duration::from_secs(5);
+        let status = loop {
+            if let Some(status) = child.try_wait().unwrap() {
+                break status;
+            }
+            if std::time::Instant::now() > deadline {
+                child.kill().unwrap();
+                panic!("child blocked on a full pipe");
+            }
+            std::thread::sleep(std::time::Duration::from_millis(10));
+        };
+        assert!(status.success(), "child exited with {}", status);
+    }
+
+    #[test]
+    fn test_prune() {
+        let dir = tempfile::tempdir().unwrap();
+        for i in 0..3 {
+            let path = dir.path().join(format!("game-{}.log", i));
+            fs::write(&path, "").unwrap();
+            let time = std::time::SystemTime::UNIX_EPOCH + std::time::Duration::from_secs(i * 60);
+            File::options()
+                .write(true)
+                .open(&path)
+                .unwrap()
+                .set_modified(time)
+                .unwrap();
+        }
+        fs::write(dir.path().join("game-0.log.1"), "").unwrap();
+
+        prune(dir.path(), 2);
+        let mut left: Vec<_> = fs::read_dir(dir.path())
+            .unwrap()
+            .map(|e| e.unwrap().file_name().into_string().unwrap())
+            .collect();
+        left.sort();
+        assert_eq!(left, vec!["game-1.log", "game-2.log"]);
+    }
+}
@@ -0,0 +1,68 @@
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+use crate::emulator::EmulatorProfile;
+use crate::scan_config::ScanConfig;
+use crate::store;
+
+/// File name of the settings store inside the app data directory
+pub const SETTINGS_FILE: &str = "settings.json";
+
+/// User preferences that are not part of the game library
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct Settings {
+    /// NACP languages to show game titles in, most preferred first.
+    /// Titles fall back to English and then any available language.
+    #[serde(default)]
+    pub preferred_languages: Vec<String>,
+    /// Use tinfoil.media and placeholder URLs for games without an icon
+    /// of their own. Off by default since they break offline.
+    #[serde(default)]
+    pub remote_icons: bool,
+    /// Custom emulators, and replacements for built-in ones with the same ID
+    #[serde(default)]
+    pub emulator_profiles: Vec<EmulatorProfile>,
+    /// Also save favorites toggled here in the emulator's own data
+    #[serde(default)]
+    pub sync_favorites: bool,
+    /// Where and how game scans look
+    #[serde(default)]
+    pub scan: ScanConfig,
+}
+
+/// Settings together with the file they are stored in
+#[derive(Debug)]
+pub struct SettingsStore {
+    path: PathBuf,
+    pub settings: Settings,
+}
+
+impl SettingsStore {
+    /// Load settings from `path`, falling back to the backup copy and then
+    /// to defaults if the file is unreadable
+    pub fn load(path: PathBuf) -> Self {
+        let read = |path: &std::path::Path| -> Result<Option<Settings>, String> {
+            store::read_json(path)?
+                .map(|value| serde_json::from_value(value).map_err(|e| e.to_string()))
+                .transpose()
+        };
+
+        let settings = match read(&path) {
+            Ok(settings) => settings.unwrap_or_default(),
+            Err(e) => {
+                log::warn!("Failed to load settings {}: {}", path.display(), e);
+                store::set_aside_corrupt(&path);
+                read(&store::backup_path(&path))
+                    .ok()
+                    .flatten()
+                    .unwrap_or_default()
+            }
+        };
+
+        SettingsStore { path, settings }
+    }
+
+    pub fn save(&self) -> Result<(), String> {
+        store::write_json_atomic(&self.path, &self.settings)
+    }
+}
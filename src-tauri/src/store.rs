@@ -0,0 +1,62 @@
+//! Helpers for the JSON files kept in the app data directory
+
+use serde::Serialize;
+use serde_json::Value;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+/// Path of the backup copy kept next to `path`
+pub fn backup_path(path: &Path) -> PathBuf {
+    path.with_extension("json.bak")
+}
+
+/// Read a JSON document. A missing file yields `None`.
+pub fn read_json(path: &Path) -> Result<Option<Value>, String> {
+    let contents = match fs::read_to_string(path) {
+        Ok(contents) => contents,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(e.to_string()),
+    };
+
+    serde_json::from_str(&contents)
+        .map(Some)
+        .map_err(|e| e.to_string())
+}
+
+/// Write `value` as JSON. The data goes to a temporary file that is synced
+/// and then renamed over the old one, so a crash mid-write leaves either
+/// the old or the new file, never a truncated one. The previous version is
+/// kept as a backup.
+pub fn write_json_atomic<T: Serialize>(path: &Path, value: &T) -> Result<(), String> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
+
+    let json = serde_json::to_vec_pretty(value).map_err(|e| e.to_string())?;
+
+    let tmp_path = path.with_extension("json.tmp");
+    {
+        let mut file = fs::File::create(&tmp_path).map_err(|e| e.to_string())?;
+        file.write_all(&json).map_err(|e| e.to_string())?;
+        file.sync_all().map_err(|e| e.to_string())?;
+    }
+
+    if path.exists() {
+        // Best effort: the backup only matters if the main file gets damaged
+        let _ = fs::copy(path, backup_path(path));
+    }
+
+    fs::rename(&tmp_path, path).map_err(|e| e.to_string())
+}
+
+/// Keep an unreadable file around for inspection instead of overwriting
+/// it on the next save
+pub fn set_aside_corrupt(path: &Path) {
+    if path.exists() {
+        let corrupt = path.with_extension("json.corrupt");
+        if let Err(e) = fs::rename(path, &corrupt) {
+            log::warn!("Failed to move corrupt {} aside: {}", path.display(), e);
+        }
+    }
+}
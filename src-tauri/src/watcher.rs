@@ -0,0 +1,200 @@
+//! Watches the scan roots so the library follows changes on disk
+
+use notify_debouncer_mini::notify::{RecommendedWatcher, RecursiveMode};
+use notify_debouncer_mini::{new_debouncer, DebounceEventResult, Debouncer};
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use walkdir::WalkDir;
+
+use crate::games::ScanRoot;
+
+/// Quiet time after the last event before changes are reported, so a
+/// dump being copied in is picked up once, when it is complete
+pub const DEBOUNCE: Duration = Duration::from_millis(500);
+
+/// A filesystem watcher over the scan roots that reports changed paths
+pub struct LibraryWatcher {
+    debouncer: Debouncer<RecommendedWatcher>,
+    roots: Vec<ScanRoot>,
+    watched: BTreeSet<PathBuf>,
+}
+
+impl LibraryWatcher {
+    /// Start a watcher that calls `on_change` with the paths that changed
+    /// in each debounced batch. Nothing is watched until `watch`.
+    pub fn new(mut on_change: impl FnMut(Vec<PathBuf>) + Send + 'static) -> Result<Self, String> {
+        let debouncer = new_debouncer(DEBOUNCE, move |result: DebounceEventResult| match result {
+            Ok(events) => {
+                let paths: BTreeSet<PathBuf> = events.into_iter().map(|e| e.path).collect();
+                on_change(paths.into_iter().collect());
+            }
+            Err(e) => log::warn!("File watcher error: {}", e),
+        })
+        .map_err(|e| format!("Failed to start file watcher: {}", e))?;
+
+        Ok(LibraryWatcher {
+            debouncer,
+            roots: Vec::new(),
+            watched: BTreeSet::new(),
+        })
+    }
+
+    /// Watch `roots` instead of the previous ones. Each folder a scan of
+    /// the root looks in is watched on its own, so nothing deeper than
+    /// the scan depth is. Roots that don't exist are skipped until the
+    /// next call.
+    pub fn watch(&mut self, roots: &[ScanRoot]) {
+        for path in std::mem::take(&mut self.watched) {
+            let _ = self.debouncer.watcher().unwatch(&path);
+        }
+
+        self.roots = watch_list(roots).into_iter().cloned().collect();
+        for root in self.roots.clone() {
+            self.watch_tree(&root.path, root.max_depth);
+        }
+    }
+
+    /// Watch folders created since `watch` that are within a root's depth
+    pub fn watch_new_dirs(&mut self, paths: &[PathBuf]) {
+        for path in paths.iter().filter(|p| p.is_dir()) {
+            let levels = self
+                .roots
+                .iter()
+                .filter_map(|root| {
+                    let depth = path.strip_prefix(&root.path).ok()?.components().count();
+                    root.max_depth.checked_sub(depth)
+                })
+                .max();
+            if let Some(levels) = levels {
+                // A folder deleted and created again lost its old watches
+                self.watched.retain(|w| !w.starts_with(path));
+                self.watch_tree(path, levels);
+            }
+        }
+    }
+
+    /// Watch `dir` and the folders below it whose files are at most
+    /// `levels` deep
+    fn watch_tree(&mut self, dir: &Path, levels: usize) {
+        if levels == 0 {
+            return;
+        }
+        let dirs = WalkDir::new(dir)
+            .max_depth(levels - 1)
+            .into_iter()
+            .filter_map(|e| e.ok())
+            .filter(|e| e.file_type().is_dir());
+        for entry in dirs {
+            if self.watched.contains(entry.path()) {
+                continue;
+            }
+            match self
+                .debouncer
+                .watcher()
+                .watch(entry.path(), RecursiveMode::NonRecursive)
+            {
+                Ok(()) => {
+                    self.watched.insert(entry.into_path());
+                }
+                Err(e) => log::debug!("Not watching {}: {}", entry.path().display(), e),
+            }
+        }
+    }
+}
+
+/// Existing roots that another root doesn't already reach as deep
+fn watch_list(roots: &[ScanRoot]) -> Vec<&ScanRoot> {
+    let mut roots: Vec<&ScanRoot> = roots.iter().filter(|r| r.path.is_dir()).collect();
+    // Parents first, deepest first for the same folder
+    roots.sort_by(|a, b| a.path.cmp(&b.path).then(b.max_depth.cmp(&a.max_depth)));
+
+    let mut list: Vec<&ScanRoot> = Vec::new();
+    for root in roots {
+        let covered = list.iter().any(|w| {
+            root.path
+                .strip_prefix(&w.path)
+                .is_ok_and(|rest| rest.components().count() + root.max_depth <= w.max_depth)
+        });
+        if !covered {
+            list.push(root);
+        }
+    }
+    list
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use std::sync::mpsc;
+
+    fn root(path: &Path, max_depth: usize) -> ScanRoot {
+        ScanRoot {
+            path: path.to_path_buf(),
+            emulator: "yuzu".to_string(),
+            max_depth,
+        }
+    }
+
+    #[test]
+    fn test_watch_list() {
+        let dir = tempfile::tempdir().unwrap();
+        let games = dir.path().join("Games");
+        fs::create_dir_all(games.join("Switch")).unwrap();
+
+        let roots = vec![
+            root(&games.join("Switch"), 2),
+            root(&games, 1),
+            root(&games, 3),
+            root(&dir.path().join("missing"), 3),
+        ];
+        let list: Vec<_> = watch_list(&roots).into_iter().cloned().collect();
+        assert_eq!(list, vec![root(&games, 3)]);
+
+        // The parent doesn't reach as deep as the subfolder's own depth
+        let roots = vec![root(&games, 3), root(&games.join("Switch"), 3)];
+        assert_eq!(watch_list(&roots).len(), 2);
+    }
+
+    #[test]
+    fn test_watches_within_depth() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir_all(dir.path().join("a/b/c")).unwrap();
+        let mut watcher = LibraryWatcher::new(|_| {}).unwrap();
+
+        watcher.watch(&[root(dir.path(), 2)]);
+        let expected = BTreeSet::from([dir.path().to_path_buf(), dir.path().join("a")]);
+        assert_eq!(watcher.watched, expected);
+
+        // New folders are watched as deep as the root reaches
+        fs::create_dir_all(dir.path().join("d/e")).unwrap();
+        watcher.watch_new_dirs(&[dir.path().join("d"), dir.path().join("a/b/c")]);
+        assert!(watcher.watched.contains(&dir.path().join("d")));
+        assert!(!watcher.watched.contains(&dir.path().join("d/e")));
+        assert!(!watcher.watched.contains(&dir.path().join("a/b/c")));
+    }
+
+    #[test]
+    fn test_reports_new_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let (tx, rx) = mpsc::channel();
+        let mut watcher = LibraryWatcher::new(move |paths| {
+            let _ = tx.send(paths);
+        })
+        .unwrap();
+        watcher.watch(&[root(dir.path(), 3)]);
+
+        let game = dir.path().join("Game.nsp");
+        fs::write(&game, b"").unwrap();
+
+        let deadline = std::time::Instant::now() + Duration::from_secs(10);
+        loop {
+            let timeout = deadline.saturating_duration_since(std::time::Instant::now());
+            let paths = rx.recv_timeout(timeout).expect("no change reported");
+            if paths.iter().any(|p| p.ends_with("Game.nsp")) {
+                break;
+            }
+        }
+    }
+}
@@ -17,7 +17,7 @@ import profile2 from '/profile2.jpeg';
 import profile3 from '/profile3.jpeg';
 import wifi from '/wifi.svg';
 
-import state, { scanGames, launchGame } from '../state-tauri';
+import state, { scanGames, launchGame, watchRunningGame, watchLibrary } from '../state-tauri';
 import Carousel from './components/Carousel';
 import useDimensions from './useDimensions';
 import useLiveTime from './useLiveTime';
@@ -42,6 +42,22 @@ function Console() {
 		scanGames();
 	}, []);
 
+	// Pick up games added or removed on disk without a rescan
+	useEffect(() => {
+		const stop = watchLibrary();
+		return () => {
+			stop.then((unlisten) => unlisten());
+		};
+	}, []);
+
+	// Follow the running game so we're back home when it closes
+	useEffect(() => {
+		const stop = watchRunningGame();
+		return () => {
+			stop.then((unlisten) => unlisten());
+		};
+	}, []);
+
 	// Get the current list of games (only scanned games, no defaults)
 	const games = snap.games;
 	const currentGame = snap.selectedTitle !== null && snap.selectedTitle < games.length ? games[snap.selectedTitle] : null;
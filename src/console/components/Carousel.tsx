@@ -102,14 +102,14 @@ export function Carousel() {
 		<div className=" left-0 z-10 mt-[-0.2em] h-[27em] w-screen px-[10em] " ref={emblaRef}>
 			<div className="flex size-full items-center gap-[1.3em]">
 				{games.map((game, index) => {
-					// Use the game's icon if available, otherwise use a placeholder
+					// Use the game's icon if available, otherwise show its title
 					const imageUrl = game.icon?.startsWith('data:') 
 						? game.icon 
 						: game.icon?.startsWith('http') 
 						? game.icon 
 						: game.icon 
 						? `data:image/png;base64,${game.icon}`
-						: 'https://via.placeholder.com/512x512/151515/FFFFFF?text=No+Icon';
+						: null;
 
 					return (
 						<div
@@ -118,7 +118,13 @@ export function Carousel() {
 							key={`${game.id}-${game.path}`}
 						>
 							{/* Tile image */}
-							<img src={imageUrl} alt={game.title} className="w-full h-full object-cover" />
+							{imageUrl ? (
+								<img src={imageUrl} alt={game.title} className="w-full h-full object-cover" />
+							) : (
+								<div className="flex size-full items-center justify-center p-[1em] text-center text-[2em] text-white">
+									{game.title}
+								</div>
+							)}
 							{/* Selected tile border */}
 							{snap.selectedTitle === index && (
 								<div className="animate-borderColor pointer-events-none absolute inset-[-.95em] rounded-[.2em] border-[.5em]"></div>
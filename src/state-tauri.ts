@@ -1,4 +1,5 @@
 import { invoke } from '@tauri-apps/api/core';
+import { listen } from '@tauri-apps/api/event';
 import { proxy } from 'valtio';
 
 export interface Game {
@@ -7,6 +8,57 @@ export interface Game {
 	path: string;
 	icon: string | null;
 	emulator: string;
+	favorite: boolean;
+}
+
+export interface RunningGame {
+	game_id: string;
+	title: string;
+	emulator: string;
+	pid: number;
+	started_at: number;
+	state: 'running' | 'suspended' | 'stopping';
+	log: string | null;
+}
+
+export interface GameExit {
+	game_id: string;
+	title: string;
+	emulator: string;
+	started_at: number;
+	ended_at: number;
+	code: number | null;
+	signal: number | null;
+	duration: number;
+	log: string | null;
+	stopped: boolean;
+	crashed: boolean;
+}
+
+export interface PlayStats {
+	game_id: string;
+	total_playtime: number;
+	last_played: number;
+	sessions: number;
+	sessions_this_week: number;
+	playtime_this_week: number;
+}
+
+export interface ScanStats {
+	reused: number;
+	parsed: number;
+	removed: number;
+}
+
+export interface ScanProgress {
+	root: string;
+	files_seen: number;
+	games_found: number;
+}
+
+interface ScanResult {
+	games: Game[];
+	stats: ScanStats;
 }
 
 const state = proxy({
@@ -14,6 +66,14 @@ const state = proxy({
 	games: [] as Game[],
 	loading: false,
 	error: null as string | null,
+	runningGame: null as RunningGame | null,
+	// Set when the last game closed unexpectedly
+	lastCrash: null as GameExit | null,
+	// Most recently played first
+	playStats: [] as PlayStats[],
+	lastScan: null as ScanStats | null,
+	// Set while a scan runs
+	scanProgress: null as ScanProgress | null,
 });
 
 export default state;
@@ -22,17 +82,34 @@ export default state;
 export async function scanGames(): Promise<void> {
 	state.loading = true;
 	state.error = null;
+	const unlisten = await listen<ScanProgress>('scan-progress', (event) => {
+		state.scanProgress = event.payload;
+	});
 	try {
-		const games = await invoke<Game[]>('scan_games');
-		state.games = games;
+		const result = await invoke<ScanResult>('scan_games');
+		state.games = result.games;
+		state.lastScan = result.stats;
 	} catch (err) {
-		state.error = err as string;
-		console.error('Failed to scan games:', err);
+		// The library is unchanged after a cancelled scan
+		if (err !== 'Scan cancelled') {
+			state.error = err as string;
+			console.error('Failed to scan games:', err);
+		}
 	} finally {
+		unlisten();
+		state.scanProgress = null;
 		state.loading = false;
 	}
 }
 
+export async function cancelScan(): Promise<void> {
+	try {
+		await invoke<boolean>('cancel_scan');
+	} catch (err) {
+		console.error('Failed to cancel scan:', err);
+	}
+}
+
 export async function getGames(): Promise<void> {
 	state.loading = true;
 	state.error = null;
@@ -74,6 +151,89 @@ export async function launchGame(game: Game): Promise<void> {
 	}
 }
 
+export async function setFavorite(id: string, favorite: boolean): Promise<void> {
+	try {
+		state.games = await invoke<Game[]>('set_favorite', { id, favorite });
+	} catch (err) {
+		console.error('Failed to set favorite:', err);
+	}
+}
+
+export async function getPlayStats(): Promise<void> {
+	try {
+		state.playStats = await invoke<PlayStats[]>('get_play_stats');
+	} catch (err) {
+		console.error('Failed to get play stats:', err);
+	}
+}
+
+// Emulator output of a session, optionally only the last `lines` lines
+export async function getSessionLog(log: string, lines?: number): Promise<string> {
+	return invoke<string>('get_session_log', { log, lines });
+}
+
+// Close the running game, like "Close software" on the Switch
+export async function stopGame(): Promise<void> {
+	try {
+		await invoke('stop_game');
+	} catch (err) {
+		console.error('Failed to stop game:', err);
+	}
+}
+
+// Pause the running game to free the CPU, like sleep mode
+export async function suspendGame(): Promise<void> {
+	try {
+		state.runningGame = await invoke<RunningGame>('suspend_game');
+	} catch (err) {
+		console.error('Failed to suspend game:', err);
+	}
+}
+
+export async function resumeGame(): Promise<void> {
+	try {
+		state.runningGame = await invoke<RunningGame>('resume_game');
+	} catch (err) {
+		console.error('Failed to resume game:', err);
+	}
+}
+
+export interface LibraryChange {
+	added: string[];
+	removed: string[];
+	updated: string[];
+}
+
+// Reload the games whenever the backend sees files change in the
+// scanned folders. Returns a function that stops listening.
+export async function watchLibrary(): Promise<() => void> {
+	return listen<LibraryChange>('library-changed', () => {
+		getGames();
+	});
+}
+
+// Track the running game through the backend's process events.
+// Returns a function that stops listening.
+export async function watchRunningGame(): Promise<() => void> {
+	state.runningGame = await invoke<RunningGame | null>('get_running_game');
+
+	const unlisten = await Promise.all([
+		listen<RunningGame>('game-started', (event) => {
+			state.runningGame = event.payload;
+			state.lastCrash = null;
+		}),
+		listen<GameExit>('game-exited', () => {
+			state.runningGame = null;
+			getPlayStats();
+		}),
+		listen<GameExit>('game-crashed', (event) => {
+			state.lastCrash = event.payload;
+			console.warn(`${event.payload.title} closed unexpectedly`, event.payload);
+		}),
+	]);
+	return () => unlisten.forEach((stop) => stop());
+}
+
 // Backward compatibility with old tiles format
 export const IMAGE_RES = 512;
 export const IMAGE_RES_STR = `${IMAGE_RES}/${IMAGE_RES}`;